@@ -38,43 +38,41 @@ impl<'a> Loop<'a> {
     }
 }
 
-fn count<'a, I>(func: &UncompiledFunction<'a>, code: &mut Peekable<I>, curr:char) -> Value<'a> where I:Iterator<Item=char> {
+fn count<I>(code: &mut Peekable<I>, curr:char) -> usize where I:Iterator<Item=char> {
     let mut amount = 1us;
     while code.peek() == Some(&curr) {
         amount += 1;
         code.next();
     }
-    func.insn_of(&amount)
+    amount
 }
 
 fn compile<'a>(func: &UncompiledFunction<'a>, code: &str) {
     let ubyte = typecs::get_ubyte();;
-    let putchar_sig = get::<fn(u8)>();
-    let readchar_sig = get::<fn() -> u8>();
     let data = func[0];
     let mut current_loop = None;
     let mut code = code.chars().peekable();
     while let Some(c) = code.next() {
         match c {
             '>' => {
-                let amount = count(func, &mut code, c);
+                let amount = count(&mut code, c);
                 let new_value = data + amount;
                 func.insn_store(data, new_value);
             },
             '<' => {
-                let amount = count(func, &mut code, c);
+                let amount = count(&mut code, c);
                 let new_value = data - amount;
                 func.insn_store(data, new_value);
             },
             '+' => {
-                let amount = count(func, &mut code, c);
+                let amount = count(&mut code, c);
                 let mut value = func.insn_load_relative(data, 0, ubyte);
                 value = value + amount;
                 value = func.insn_convert(value, ubyte, false);
                 func.insn_store_relative(data, 0, value)
             },
             '-' => {
-                let amount = count(func, &mut code, c);
+                let amount = count(&mut code, c);
                 let mut value = func.insn_load_relative(data, 0, ubyte);
                 value = value - amount;
                 value = func.insn_convert(value, ubyte, false);
@@ -85,13 +83,13 @@ fn compile<'a>(func: &UncompiledFunction<'a>, code: &str) {
                     old_io::stdout().write_u8(c).unwrap();
                 }
                 let value = func.insn_load_relative(data, 0, ubyte);
-                func.insn_call_native1(Some("putchar"), putchar, putchar_sig.get(), [value], flags::NO_THROW);
+                func.insn_call_rust(Some("putchar"), putchar as extern fn(u8), &[value], flags::NO_THROW).unwrap();
             },
             ',' => {
                 extern fn readchar() -> u8 {
                     old_io::stdin().read_byte().unwrap()
                 }
-                let value = func.insn_call_native0(Some("readchar"), readchar, readchar_sig.get(), flags::NO_THROW);
+                let value = func.insn_call_rust(Some("readchar"), readchar as extern fn() -> u8, &[], flags::NO_THROW).unwrap();
                 func.insn_store_relative(data, 0, value);
             },
             '[' => {
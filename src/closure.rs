@@ -0,0 +1,108 @@
+use raw::*;
+use alloc::oom;
+use context::Builder;
+use dynamic::DynValue;
+use libc::c_void;
+use meta;
+use std::error::Error;
+use std::{fmt, intrinsics, mem};
+use std::rt::unwind;
+use types::{consts, Type, TypeRef};
+use util::{from_ptr, NativeRef};
+/// The error returned when closures can't be made on this platform
+#[derive(Copy, Debug, PartialEq)]
+pub struct ClosureUnsupported;
+impl fmt::Display for ClosureUnsupported {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}", self.description())
+    }
+}
+impl Error for ClosureUnsupported {
+    fn description(&self) -> &str {
+        "Closures are not supported on this platform"
+    }
+}
+type ClosureFn = Box<FnMut(&[DynValue]) -> DynValue + 'static>;
+/// The Rust closures behind the closures of a context, kept in its metadata
+/// so they live exactly as long as the native code that calls them
+struct ClosureData(Vec<Box<ClosureFn>>);
+/// A Rust closure that has been turned into a native function pointer with a
+/// signature, so it can be called from compiled functions and C code
+pub struct Closure<'a> {
+    code: *mut c_void,
+    signature: Type,
+    context: &'a Builder
+}
+extern fn call_closure(signature: jit_type_t, result: *mut c_void, args: *mut *mut c_void, data: *mut c_void) {
+    unsafe {
+        let outcome = unwind::try(|| {
+            let signature:TypeRef = from_ptr(signature);
+            let cb:&mut ClosureFn = mem::transmute(data);
+            let values = signature.params().enumerate().map(|(index, param)|
+                DynValue::read(*param, *args.offset(index as isize))
+            ).collect::<Vec<_>>();
+            let value = cb(values.as_slice());
+            let ret_type = signature.get_return().unwrap_or(consts::get_void());
+            if !value.fits(ret_type) {
+                panic!("Closure returned {:?}, which does not fit its signature", value)
+            }
+            value.write(result);
+        });
+        if outcome.is_err() {
+            // unwinding into the native code that called this is undefined
+            intrinsics::abort()
+        }
+    }
+}
+impl<'a> Closure<'a> {
+    /// Make a closure in the context being built that has the signature
+    /// given and runs the Rust closure given with its arguments when it is
+    /// called.
+    ///
+    /// The Rust closure must return a value that fits the return type of
+    /// the signature, or `DynValue::Void` if it has none, and the process is
+    /// aborted if it doesn't or if it panics. The Rust closure is owned by
+    /// the context and freed along with the native code, so the closure
+    /// borrows the context.
+    pub fn new<F>(ctx: &'a Builder, signature: TypeRef, cb: F) -> Result<Closure<'a>, ClosureUnsupported>
+        where F:FnMut(&[DynValue]) -> DynValue + 'static {
+        if !::supports_closures() {
+            return Err(ClosureUnsupported)
+        }
+        unsafe {
+            let signature:Type = from_ptr(jit_type_copy(signature.as_ptr()));
+            let mut data:Box<ClosureFn> = Box::new(Box::new(cb));
+            let data_ptr = &mut *data as *mut ClosureFn as *mut c_void;
+            let code = jit_closure_create(ctx.as_ptr(), signature.as_ptr(), Some(call_closure), data_ptr);
+            if code.is_null() {
+                oom()
+            }
+            meta::with_context_map(ctx.as_ptr(), |map| {
+                if map.get::<ClosureData>().is_none() {
+                    map.insert(Box::new(ClosureData(Vec::new())));
+                }
+                map.get_mut::<ClosureData>().unwrap().0.push(data);
+            });
+            Ok(Closure {
+                code: code,
+                signature: signature,
+                context: ctx
+            })
+        }
+    }
+    #[inline(always)]
+    /// Get the native function pointer of this closure
+    pub fn as_code(&self) -> *mut c_void {
+        self.code
+    }
+    #[inline(always)]
+    /// Get the signature of this closure
+    pub fn get_signature(&self) -> TypeRef {
+        *self.signature
+    }
+    #[inline(always)]
+    /// Get the context this closure was made in
+    pub fn get_context(&self) -> &'a Builder {
+        self.context
+    }
+}
@@ -1,13 +1,16 @@
 use raw::*;
-use function::UncompiledFunction;
+use exception::JitException;
+use function::{CompiledFunction, UncompiledFunction};
 use function::Abi::CDecl;
 use types::get;
-use libc::c_long;
+use libc::{c_long, c_void};
+use slice::RawSlice;
 use value::Value;
 use types::{consts, CowType, Type};
 use util::{from_ptr, NativeRef};
 use std::borrow::IntoCow;
 use std::ffi::CString;
+use std::{mem, ptr};
 /// A type that can be compiled into a LibJIT representation
 pub trait Compile {
     /// Get a JIT representation of this value
@@ -88,6 +91,40 @@ impl<T> Compile for &'static T where T:Compile {
         Type::new_pointer(get::<T>().get()).into_cow()
     }
 }
+impl<T> Compile for RawSlice<T> where T:Compile {
+    /// Compile the slice into a struct of a pointer to its first element and
+    /// its length, which `JitSlice::from_value` can unpack
+    fn compile<'a>(&self, func:&UncompiledFunction<'a>) -> Value<'a> {
+        let ty = get::<RawSlice<T>>();
+        let slice = Value::new(func, ty.get());
+        let address = func.insn_address_of(slice);
+        let mut fields = ty.get().fields();
+        func.insn_store_relative(address, fields.next().unwrap().get_offset(), func.insn_of(&self.as_ptr()));
+        func.insn_store_relative(address, fields.next().unwrap().get_offset(), func.insn_of(&self.len()));
+        slice
+    }
+    #[inline(always)]
+    fn get_type() -> CowType<'static> {
+        let elem = get::<T>();
+        let ptr = Type::new_pointer(elem.get());
+        let mut fields = [*ptr, consts::get_nuint()];
+        Type::new_struct(fields.as_mut_slice()).into_cow()
+    }
+}
+impl<T> Compile for &'static [T] where T:Compile {
+    /// Compile the slice the same way as a `RawSlice`
+    ///
+    /// Functions should take a `RawSlice` as a parameter instead of a slice,
+    /// since Rust doesn't promise to lay slices out like a `RawSlice` across
+    /// an `extern fn` call.
+    fn compile<'a>(&self, func:&UncompiledFunction<'a>) -> Value<'a> {
+        RawSlice::new(*self).compile(func)
+    }
+    #[inline(always)]
+    fn get_type() -> CowType<'static> {
+        <RawSlice<T> as Compile>::get_type()
+    }
+}
 impl Compile for CString {
     #[inline(always)]
     fn compile<'a>(&self, func:&UncompiledFunction<'a>) -> Value<'a> {
@@ -106,4 +143,39 @@ compile_func!(fn() -> R, fn() -> R, extern fn() -> R);
 compile_func!(fn(A) -> R, fn(A) -> R, extern fn(A) -> R);
 compile_func!(fn(A, B) -> R, fn(A, B) -> R, extern fn(A, B) -> R);
 compile_func!(fn(A, B, C) -> R, fn(A, B, C) -> R, extern fn(A, B, C) -> R);
-compile_func!(fn(A, B, C, D) -> R, fn(A, B, C, D) -> R, extern fn(A, B, C, D) -> R);
\ No newline at end of file
+compile_func!(fn(A, B, C, D) -> R, fn(A, B, C, D) -> R, extern fn(A, B, C, D) -> R);
+compile_func!(fn(A, B, C, D, E) -> R, fn(A, B, C, D, E) -> R, extern fn(A, B, C, D, E) -> R);
+compile_func!(fn(A, B, C, D, E, F) -> R, fn(A, B, C, D, E, F) -> R, extern fn(A, B, C, D, E, F) -> R);
+/// Apply the function given to the arguments given, with its return value
+/// written into a buffer that is only read as an `R` once the call succeeds
+unsafe fn apply_returning<R>(func: &CompiledFunction, args: &mut [*mut c_void]) -> Result<R, JitException> {
+    assert!(mem::align_of::<R>() <= mem::align_of::<u64>());
+    let mut ret:Vec<u64> = Vec::with_capacity((mem::size_of::<R>() + 7) / 8);
+    try!(func.apply(args, ret.as_mut_ptr() as *mut c_void));
+    Ok(ptr::read(ret.as_ptr() as *const R))
+}
+/// A native function pointer type, which compiled functions can be turned
+/// into and called through
+pub trait ExternFn : Compile + Copy {
+    /// The arguments this function takes, as a tuple
+    type Args;
+    /// The type this function returns
+    type Output;
+    /// Make a function pointer of this type that points to the code given
+    unsafe fn from_code(code: *mut c_void) -> Self;
+    /// Get a pointer to the code of this function
+    fn as_code(self) -> *mut c_void;
+    /// Call the compiled function given through LibJIT with the arguments
+    /// given, catching any exception it throws
+    ///
+    /// This is unsafe because the compiled function's signature must match
+    /// this type
+    unsafe fn apply(func: &CompiledFunction, args: Self::Args) -> Result<Self::Output, JitException>;
+}
+extern_fn!( => );
+extern_fn!(A => a);
+extern_fn!(A, B => a, b);
+extern_fn!(A, B, C => a, b, c);
+extern_fn!(A, B, C, D => a, b, c, d);
+extern_fn!(A, B, C, D, E => a, b, c, d, e);
+extern_fn!(A, B, C, D, E, F => a, b, c, d, e, f);
\ No newline at end of file
@@ -1,11 +1,82 @@
 use raw::*;
 use alloc::oom;
 use std::marker::{ContravariantLifetime, NoCopy};
-use std::any::TypeId;
-use std::{hash, mem, ptr};
+use std::error::Error;
+use std::{fmt, mem, ptr};
 use std::iter::IntoIterator;
+use std::rc::Rc;
+use exception::{self, BuiltinException};
+use libc::{c_int, c_void};
+use memory::{self, DefaultMemoryManager, MemoryManager};
+use meta;
+use trace::{self, Frame};
+use function::RESULT_OK;
 use util::{from_ptr, NativeRef};
 use {AnyFunction, CompiledFunction, TypeRef, UncompiledFunction};
+/// The on-demand driver hook of a context, kept in its metadata
+struct OnDemandDriver(Rc<Box<Fn(&AnyFunction, &mut FnMut()) + 'static>>);
+/// A standard LibJIT option that can be set on a context
+#[repr(i32)]
+#[derive(Copy, Debug, Eq, PartialEq)]
+pub enum ContextOption {
+    /// The maximum size of the code cache in bytes
+    CacheLimit = 10000,
+    /// The size of a page of the code cache in bytes
+    CachePageSize = 10001,
+    /// Whether functions are compiled before being written to ELF binaries
+    PreCompile = 10002,
+    /// Whether constant folding is turned off
+    DontFold = 10003,
+    /// Whether position-independent code is generated
+    PositionIndependent = 10004,
+    /// The maximum number of pages a single function can take up
+    CacheMaxPageFactor = 10005
+}
+/// Build and compile a function with its on-demand compiler, like LibJIT's
+/// default driver does
+unsafe fn compile_on_demand(ctx: jit_context_t, func: jit_function_t) -> c_int {
+    jit_context_build_start(ctx);
+    let result = if jit_function_is_compiled(func) != 0 {
+        RESULT_OK
+    } else {
+        match jit_function_get_on_demand_compiler(func) {
+            Some(on_demand) => {
+                let result = on_demand(func);
+                if result == RESULT_OK && jit_function_is_compiled(func) == 0 && jit_function_compile(func) == 0 {
+                    BuiltinException::CompileError as c_int
+                } else {
+                    result
+                }
+            },
+            None => BuiltinException::CompileError as c_int
+        }
+    };
+    jit_context_build_end(ctx);
+    result
+}
+extern fn drive_on_demand(func: jit_function_t) -> *mut c_void {
+    unsafe {
+        let ctx = jit_function_get_context(func);
+        // keep the driver alive even if it replaces itself while it runs
+        let driver = meta::with_context_map(ctx, |map| map.get::<OnDemandDriver>().map(|driver| driver.0.clone()));
+        let mut result = BuiltinException::CompileError as c_int;
+        {
+            let mut compile = || result = compile_on_demand(ctx, func);
+            match driver {
+                Some(driver) => {
+                    let any:AnyFunction = from_ptr(func);
+                    (**driver)(&any, &mut compile)
+                },
+                None => compile()
+            }
+        }
+        if jit_function_is_compiled(func) == 0 {
+            let builtin = BuiltinException::from_code(result).unwrap_or(BuiltinException::CompileError);
+            exception::throw_builtin(builtin);
+        }
+        jit_function_to_closure(func)
+    }
+}
 /// Holds all of the functions you have built and compiled. There can be
 /// multiple, but normally there is only one.
 native_ref!(Context {
@@ -31,6 +102,180 @@ impl NativeRef for Builder {
     }
 }
 
+/// An error from building a context with invalid options
+#[derive(Copy, Debug, Eq, PartialEq)]
+pub enum ContextError {
+    /// The cache page size given is not a power of two
+    PageSizeNotPowerOfTwo(usize),
+    /// The cache limit given is smaller than a single cache page
+    CacheLimitTooSmall {
+        /// The cache limit in bytes
+        limit: usize,
+        /// The cache page size in bytes
+        page_size: usize
+    },
+    /// The maximum page factor given is zero
+    ZeroPageFactor,
+    /// Both a memory manager and a raw memory manager were given
+    ConflictingMemoryManagers
+}
+impl fmt::Display for ContextError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ContextError::PageSizeNotPowerOfTwo(size) =>
+                write!(fmt, "cache page size {} is not a power of two", size),
+            ContextError::CacheLimitTooSmall { limit, page_size } =>
+                write!(fmt, "cache limit {} is smaller than the page size {}", limit, page_size),
+            ContextError::ZeroPageFactor =>
+                write!(fmt, "cache max page factor is zero"),
+            ContextError::ConflictingMemoryManagers =>
+                write!(fmt, "both a memory manager and a raw memory manager were given")
+        }
+    }
+}
+impl Error for ContextError {
+    fn description(&self) -> &str {
+        match *self {
+            ContextError::PageSizeNotPowerOfTwo(_) => "Cache page size is not a power of two",
+            ContextError::CacheLimitTooSmall { .. } => "Cache limit is smaller than a page",
+            ContextError::ZeroPageFactor => "Cache max page factor is zero",
+            ContextError::ConflictingMemoryManagers => "More than one memory manager was given"
+        }
+    }
+}
+/// Configures the options of a context before it is made
+pub struct ContextBuilder {
+    cache_limit: Option<usize>,
+    cache_page_size: Option<usize>,
+    cache_max_page_factor: Option<usize>,
+    pre_compile: bool,
+    dont_fold: bool,
+    position_independent: bool,
+    memory_manager: Option<jit_memory_manager_t>,
+    manager: Option<Box<MemoryManager>>
+}
+impl ContextBuilder {
+    #[inline(always)]
+    /// Make a builder with LibJIT's default options
+    pub fn new() -> ContextBuilder {
+        ContextBuilder {
+            cache_limit: None,
+            cache_page_size: None,
+            cache_max_page_factor: None,
+            pre_compile: false,
+            dont_fold: false,
+            position_independent: false,
+            memory_manager: None,
+            manager: None
+        }
+    }
+    #[inline(always)]
+    /// Limit the size of the code cache to the number of bytes given
+    pub fn cache_limit(mut self, bytes: usize) -> ContextBuilder {
+        self.cache_limit = Some(bytes);
+        self
+    }
+    #[inline(always)]
+    /// Set the size of a code cache page in bytes, which must be a power of two
+    pub fn cache_page_size(mut self, bytes: usize) -> ContextBuilder {
+        self.cache_page_size = Some(bytes);
+        self
+    }
+    #[inline(always)]
+    /// Set the maximum number of pages a single function can take up
+    pub fn cache_max_page_factor(mut self, factor: usize) -> ContextBuilder {
+        self.cache_max_page_factor = Some(factor);
+        self
+    }
+    #[inline(always)]
+    /// Set whether functions are compiled before being written to ELF binaries
+    pub fn pre_compile(mut self, pre_compile: bool) -> ContextBuilder {
+        self.pre_compile = pre_compile;
+        self
+    }
+    #[inline(always)]
+    /// Set whether constant folding is turned off
+    pub fn dont_fold(mut self, dont_fold: bool) -> ContextBuilder {
+        self.dont_fold = dont_fold;
+        self
+    }
+    #[inline(always)]
+    /// Set whether position-independent code is generated
+    pub fn position_independent(mut self, position_independent: bool) -> ContextBuilder {
+        self.position_independent = position_independent;
+        self
+    }
+    #[inline(always)]
+    /// Use the memory manager given for the code cache
+    ///
+    /// Functions only know where their native code ends in contexts with a
+    /// memory manager, so this is how to make `CompiledFunction::native_code`
+    /// work, even with `DefaultMemoryManager`.
+    pub fn memory_manager<M>(mut self, manager: M) -> ContextBuilder where M:MemoryManager {
+        self.manager = Some(Box::new(manager));
+        self
+    }
+    #[inline(always)]
+    /// Use the raw LibJIT memory manager given for the code cache
+    ///
+    /// This can't be combined with `memory_manager`.
+    ///
+    /// This is unsafe because the memory manager must be valid for as long as
+    /// the context lives.
+    pub unsafe fn raw_memory_manager(mut self, manager: jit_memory_manager_t) -> ContextBuilder {
+        self.memory_manager = Some(manager);
+        self
+    }
+    /// Check the options are valid and make a context with them
+    pub fn build(self) -> Result<Context, ContextError> {
+        if let Some(page_size) = self.cache_page_size {
+            if !page_size.is_power_of_two() {
+                return Err(ContextError::PageSizeNotPowerOfTwo(page_size))
+            }
+            if let Some(limit) = self.cache_limit {
+                if limit < page_size {
+                    return Err(ContextError::CacheLimitTooSmall {
+                        limit: limit,
+                        page_size: page_size
+                    })
+                }
+            }
+        }
+        if self.cache_max_page_factor == Some(0) {
+            return Err(ContextError::ZeroPageFactor)
+        }
+        if self.manager.is_some() && self.memory_manager.is_some() {
+            return Err(ContextError::ConflictingMemoryManagers)
+        }
+        let ctx = Context::new();
+        if let Some(manager) = self.manager {
+            memory::install(&ctx, manager);
+        }
+        if let Some(manager) = self.memory_manager {
+            let manager = unsafe { DefaultMemoryManager::from_raw(manager) };
+            memory::install(&ctx, Box::new(manager));
+        }
+        if let Some(limit) = self.cache_limit {
+            ctx.set_option(ContextOption::CacheLimit, limit);
+        }
+        if let Some(page_size) = self.cache_page_size {
+            ctx.set_option(ContextOption::CachePageSize, page_size);
+        }
+        if let Some(factor) = self.cache_max_page_factor {
+            ctx.set_option(ContextOption::CacheMaxPageFactor, factor);
+        }
+        if self.pre_compile {
+            ctx.set_option(ContextOption::PreCompile, 1);
+        }
+        if self.dont_fold {
+            ctx.set_option(ContextOption::DontFold, 1);
+        }
+        if self.position_independent {
+            ctx.set_option(ContextOption::PositionIndependent, 1);
+        }
+        Ok(ctx)
+    }
+}
 impl Context {
     unsafe fn as_builder(&mut self) -> Builder {
         from_ptr(self.as_ptr())
@@ -42,24 +287,45 @@ impl Context {
             from_ptr(jit_context_create())
         }
     }
-    /// Get the tagged metadata of an object
+    /// Get the metadata of the type given that is attached to this context
     pub fn get_meta<T>(&self) -> Option<&T> where T:'static {
         unsafe {
-            let id = hash::hash::<TypeId, hash::SipHasher>(&TypeId::of::<T>());
-            mem::transmute(jit_context_get_meta(self.as_ptr(), id as i32))
+            let data = meta::with_context_map(self.as_ptr(), |map| map.get::<T>().map(|data| data as *const T));
+            data.map(|data| &*data)
         }
     }
-
-    /// Tag the context with some metadata
+    /// Attach some metadata to this context, replacing any of the same type.
+    /// The old metadata is kept until the context is dropped, since it could
+    /// still be borrowed.
     pub fn set_meta<T>(&self, data: Box<T>) where T:'static {
         unsafe {
-            let id = hash::hash::<TypeId, hash::SipHasher>(&TypeId::of::<T>());
-            if jit_context_set_meta(self.as_ptr(), id as i32, mem::transmute(data), Some(::free_data::<T>)) == 0 {
+            meta::with_context_map(self.as_ptr(), |map| map.replace(data))
+        }
+    }
+    /// Detach the metadata of the type given from this context and return it
+    pub fn remove_meta<T>(&mut self) -> Option<Box<T>> where T:'static {
+        unsafe {
+            meta::with_context_map(self.as_ptr(), |map| map.remove())
+        }
+    }
+    #[inline(always)]
+    /// Set a standard LibJIT option on this context
+    pub fn set_option(&self, option: ContextOption, value: usize) {
+        unsafe {
+            if jit_context_set_meta_numeric(self.as_ptr(), option as c_int, value as jit_nuint) == 0 {
                 oom()
             }
         }
     }
     #[inline(always)]
+    /// Get a standard LibJIT option of this context, which is 0 if it was
+    /// never set
+    pub fn get_option(&self, option: ContextOption) -> usize {
+        unsafe {
+            jit_context_get_meta_numeric(self.as_ptr(), option as c_int) as usize
+        }
+    }
+    #[inline(always)]
     /// Lock the context so you can safely generate IR
     pub fn build<R, F:FnOnce(&Builder) -> R>(&mut self, cb: F) -> R {
         unsafe {
@@ -82,6 +348,41 @@ impl Context {
             func.compile()
         }
     }
+    /// Make a function on this context whose body is built by the closure
+    /// given the first time it is called instead of now
+    pub fn build_on_demand<'a, F>(&'a mut self, signature: TypeRef, cb: F) -> CompiledFunction<'a>
+        where F:FnMut(&UncompiledFunction) + 'static {
+        unsafe {
+            jit_context_build_start(self.as_ptr());
+            let builder = self.as_builder();
+            let func = UncompiledFunction::new(mem::copy_lifetime(self, &builder), signature);
+            let func = func.set_on_demand(cb);
+            jit_context_build_end(self.as_ptr());
+            func
+        }
+    }
+    /// Set a hook that runs whenever a function in this context is compiled
+    /// on demand, so it can do its own locking or logging. It gets the
+    /// function and a thunk that compiles it, which it should call.
+    pub fn set_on_demand_driver<F>(&self, driver: F) where F:Fn(&AnyFunction, &mut FnMut()) + 'static {
+        unsafe {
+            let driver = OnDemandDriver(Rc::new(Box::new(driver)));
+            meta::with_context_map(self.as_ptr(), |map| map.insert(Box::new(driver)));
+            jit_context_set_on_demand_driver(self.as_ptr(), Some(drive_on_demand));
+        }
+    }
+    /// Walk up the call stack, resolving each frame to a function inside this
+    /// context, until the callback returns false
+    pub fn unwind<'a, F>(&'a self, cb: F) where F:FnMut(Frame<'a>) -> bool {
+        trace::unwind(self, cb)
+    }
+    /// Find the compiled function inside this context whose code contains
+    /// the native program counter given
+    pub fn function_from_pc<'a>(&'a self, pc: *mut c_void) -> Option<CompiledFunction<'a>> {
+        unsafe {
+            from_ptr(jit_function_from_pc(self.as_ptr(), pc, ptr::null_mut()))
+        }
+    }
     /// Iterate through the functions contained inside this context
     pub fn functions<'a>(&'a self) -> Functions<'a> {
         unsafe {
@@ -0,0 +1,302 @@
+//! Debugging support for compiled functions, using breakpoints marked with
+//! `insn_mark_breakpoint`
+use raw::*;
+use context::Context;
+use function::AnyFunction;
+use meta;
+use std::rc::Rc;
+use std::marker::ContravariantLifetime;
+use std::{mem, ptr};
+use trace::StackTrace;
+use util::{from_ptr, NativeRef};
+pub use self::flags::BreakpointFlags;
+/// The identifier of a thread being debugged
+pub type ThreadId = jit_debugger_thread_id_t;
+/// The identifier of a breakpoint
+pub type BreakpointId = jit_debugger_breakpoint_id_t;
+/// The `data1` of a breakpoint marked at the start of a line
+pub static DATA1_LINE: isize = 10000;
+/// The `data1` of a breakpoint marked when a function is entered
+pub static DATA1_ENTER: isize = 10001;
+/// The `data1` of a breakpoint marked when a function is left
+pub static DATA1_LEAVE: isize = 10002;
+/// The `data1` of a breakpoint marked when an exception is thrown
+pub static DATA1_THROW: isize = 10003;
+pub mod flags {
+    use libc::c_int;
+    /// Which fields of a breakpoint must match for it to be hit
+    bitflags!(
+        flags BreakpointFlags: c_int {
+            /// When the thread must match
+            const THREAD = 1,
+            /// When the function must match
+            const FUNCTION = 2,
+            /// When `data1` must match
+            const DATA1 = 4,
+            /// When `data2` must match
+            const DATA2 = 8
+        }
+    );
+}
+/// The kind of event a debugger can receive
+#[derive(Copy, Debug, Eq, PartialEq)]
+pub enum EventKind {
+    /// The debugger was told to quit
+    Quit,
+    /// A breakpoint was hit that was set with `add_breakpoint`
+    HardBreakpoint,
+    /// A thread stopped because of stepping
+    SoftBreakpoint,
+    /// A thread stopped because of `stop`
+    UserBreakpoint,
+    /// A thread attached itself to the debugger
+    AttachThread,
+    /// A thread detached itself from the debugger
+    DetachThread
+}
+impl EventKind {
+    fn from_code(code: i32) -> Option<EventKind> {
+        Some(match code {
+            0 => EventKind::Quit,
+            1 => EventKind::HardBreakpoint,
+            2 => EventKind::SoftBreakpoint,
+            3 => EventKind::UserBreakpoint,
+            4 => EventKind::AttachThread,
+            5 => EventKind::DetachThread,
+            _ => return None
+        })
+    }
+}
+/// Something that happened to a thread being debugged
+pub struct Event<'a> {
+    /// The kind of event this is
+    pub kind: EventKind,
+    /// The thread the event happened on
+    pub thread: ThreadId,
+    /// The function the thread stopped in, if it stopped
+    pub function: Option<AnyFunction<'a>>,
+    /// The first value given to the breakpoint
+    pub data1: isize,
+    /// The second value given to the breakpoint
+    pub data2: isize,
+    /// The breakpoint that was hit, if it was a hard breakpoint
+    pub breakpoint: BreakpointId,
+    /// The call stack when the thread stopped
+    pub trace: Option<StackTrace>
+}
+/// A breakpoint that stops threads when a marked point matches its fields
+pub struct Breakpoint<'a> {
+    /// Which of the fields must match
+    pub flags: BreakpointFlags,
+    /// The thread to stop
+    pub thread: ThreadId,
+    /// The function to stop in
+    pub function: Option<&'a AnyFunction<'a>>,
+    /// The first value the marked point must have
+    pub data1: isize,
+    /// The second value the marked point must have
+    pub data2: isize
+}
+/// Controls the threads that run compiled functions in a context, so they
+/// can be stopped at breakpoints and stepped through
+native_ref!(Debugger ContravariantLifetime {
+    _debugger: jit_debugger_t
+});
+impl<'a> Debugger<'a> {
+    #[inline(always)]
+    /// Make a debugger for the context given
+    pub fn new(ctx: &'a Context) -> Debugger<'a> {
+        unsafe {
+            from_ptr(jit_debugger_create(ctx.as_ptr()))
+        }
+    }
+    #[inline(always)]
+    /// Get the identifier of the current thread
+    pub fn get_self(&self) -> ThreadId {
+        unsafe {
+            jit_debugger_get_self(self.as_ptr())
+        }
+    }
+    #[inline(always)]
+    /// Attach the current thread to this debugger, stopping it immediately
+    /// if `stop_immediately` is true
+    pub fn attach_self(&self, stop_immediately: bool) {
+        unsafe {
+            jit_debugger_attach_self(self.as_ptr(), stop_immediately as i32);
+        }
+    }
+    #[inline(always)]
+    /// Detach the current thread from this debugger
+    pub fn detach_self(&self) {
+        unsafe {
+            jit_debugger_detach_self(self.as_ptr());
+        }
+    }
+    #[inline(always)]
+    /// Set whether the current thread can be stopped at breakpoints
+    pub fn set_breakable(&self, breakable: bool) {
+        unsafe {
+            jit_debugger_set_breakable(self.as_ptr(), ptr::null(), breakable as i32);
+        }
+    }
+    /// Wait for the next event, for up to `timeout` milliseconds or forever
+    /// if it is `None`
+    pub fn wait_event(&self, timeout: Option<i32>) -> Option<Event<'a>> {
+        unsafe {
+            let mut event:jit_debugger_event_t = mem::zeroed();
+            if jit_debugger_wait_event(self.as_ptr(), &mut event, timeout.unwrap_or(-1)) == 0 {
+                return None
+            }
+            let trace = from_ptr(event.trace);
+            EventKind::from_code(event._type).map(|kind| Event {
+                kind: kind,
+                thread: event.thread,
+                function: from_ptr(event.function),
+                data1: event.data1 as isize,
+                data2: event.data2 as isize,
+                breakpoint: event.id,
+                trace: trace
+            })
+        }
+    }
+    #[inline(always)]
+    /// Iterate through events as they happen until the debugger quits
+    pub fn events<'b>(&'b self) -> Events<'a, 'b> {
+        Events {
+            debugger: self
+        }
+    }
+    /// Add a breakpoint and return its identifier
+    pub fn add_breakpoint(&self, breakpoint: &Breakpoint) -> BreakpointId {
+        unsafe {
+            let mut info = Struct_jit_debugger_breakpoint_info {
+                flags: breakpoint.flags.bits(),
+                thread: breakpoint.thread,
+                function: breakpoint.function.map(|func| func.as_ptr()).unwrap_or(ptr::null_mut()),
+                data1: breakpoint.data1 as jit_nint,
+                data2: breakpoint.data2 as jit_nint
+            };
+            jit_debugger_add_breakpoint(self.as_ptr(), &mut info)
+        }
+    }
+    #[inline(always)]
+    /// Remove the breakpoint with the identifier given
+    pub fn remove_breakpoint(&self, id: BreakpointId) {
+        unsafe {
+            jit_debugger_remove_breakpoint(self.as_ptr(), id);
+        }
+    }
+    #[inline(always)]
+    /// Remove all the breakpoints
+    pub fn remove_all_breakpoints(&self) {
+        unsafe {
+            jit_debugger_remove_all_breakpoints(self.as_ptr());
+        }
+    }
+    #[inline(always)]
+    /// Check if the thread given is still alive
+    pub fn is_alive(&self, thread: ThreadId) -> bool {
+        unsafe {
+            jit_debugger_is_alive(self.as_ptr(), thread) != 0
+        }
+    }
+    #[inline(always)]
+    /// Check if the thread given is running rather than stopped
+    pub fn is_running(&self, thread: ThreadId) -> bool {
+        unsafe {
+            jit_debugger_is_running(self.as_ptr(), thread) != 0
+        }
+    }
+    #[inline(always)]
+    /// Let the thread given run until it hits a breakpoint
+    pub fn run(&self, thread: ThreadId) {
+        unsafe {
+            jit_debugger_run(self.as_ptr(), thread);
+        }
+    }
+    #[inline(always)]
+    /// Let the thread given run to the next marked point, stepping into calls
+    pub fn step(&self, thread: ThreadId) {
+        unsafe {
+            jit_debugger_step(self.as_ptr(), thread);
+        }
+    }
+    #[inline(always)]
+    /// Let the thread given run to the next marked point, stepping over calls
+    pub fn next(&self, thread: ThreadId) {
+        unsafe {
+            jit_debugger_next(self.as_ptr(), thread);
+        }
+    }
+    #[inline(always)]
+    /// Let the thread given run until it returns from its current function
+    pub fn finish(&self, thread: ThreadId) {
+        unsafe {
+            jit_debugger_finish(self.as_ptr(), thread);
+        }
+    }
+    #[inline(always)]
+    /// Stop all the threads at their next marked point
+    pub fn stop(&self) {
+        unsafe {
+            jit_debugger_break(self.as_ptr());
+        }
+    }
+    #[inline(always)]
+    /// Tell the debugger to quit, which sends a `Quit` event
+    pub fn quit(&self) {
+        unsafe {
+            jit_debugger_quit(self.as_ptr());
+        }
+    }
+}
+#[unsafe_destructor]
+impl<'a> Drop for Debugger<'a> {
+    #[inline(always)]
+    fn drop(&mut self) {
+        unsafe {
+            jit_debugger_destroy(self.as_ptr());
+        }
+    }
+}
+/// Iterates through the events of a debugger until it quits
+pub struct Events<'a:'b, 'b> {
+    debugger: &'b Debugger<'a>
+}
+impl<'a, 'b> Iterator for Events<'a, 'b> {
+    type Item = Event<'a>;
+    fn next(&mut self) -> Option<Event<'a>> {
+        match self.debugger.wait_event(None) {
+            Some(Event { kind: EventKind::Quit, .. }) | None => None,
+            event => event
+        }
+    }
+}
+/// The breakpoint hook of a context, kept in its metadata
+struct Hook(Rc<Box<Fn(&AnyFunction, isize, isize) + 'static>>);
+extern fn call_hook(func: jit_function_t, data1: jit_nint, data2: jit_nint) {
+    unsafe {
+        let ctx = jit_function_get_context(func);
+        // keep the hook alive even if it is cleared while it runs
+        if let Some(hook) = meta::with_context_map(ctx, |map| map.get::<Hook>().map(|hook| hook.0.clone())) {
+            let func:AnyFunction = from_ptr(func);
+            (**hook)(&func, data1 as isize, data2 as isize);
+        }
+    }
+}
+/// Set a hook that is called whenever a marked breakpoint is reached in the
+/// context given, instead of stopping for a debugger
+pub fn set_hook<F>(ctx: &Context, hook: F) where F:Fn(&AnyFunction, isize, isize) + 'static {
+    unsafe {
+        let hook = Hook(Rc::new(Box::new(hook)));
+        meta::with_context_map(ctx.as_ptr(), |map| map.insert(Box::new(hook)));
+        jit_debugger_set_hook(ctx.as_ptr(), Some(call_hook));
+    }
+}
+/// Remove the hook set by `set_hook` from the context given
+pub fn clear_hook(ctx: &Context) {
+    unsafe {
+        jit_debugger_set_hook(ctx.as_ptr(), None);
+        meta::with_context_map(ctx.as_ptr(), |map| map.remove::<Hook>());
+    }
+}
@@ -0,0 +1,497 @@
+//! A small x86-64 disassembler for checking the code LibJIT generates
+//!
+//! This only knows the general-purpose and scalar SSE instructions LibJIT
+//! emits, and decodes anything else as `(bad)` followed by a single byte.
+use std::num::SignedInt;
+
+static REGS64: [&'static str; 16] = [
+    "rax", "rcx", "rdx", "rbx", "rsp", "rbp", "rsi", "rdi",
+    "r8", "r9", "r10", "r11", "r12", "r13", "r14", "r15"
+];
+static REGS32: [&'static str; 16] = [
+    "eax", "ecx", "edx", "ebx", "esp", "ebp", "esi", "edi",
+    "r8d", "r9d", "r10d", "r11d", "r12d", "r13d", "r14d", "r15d"
+];
+static REGS16: [&'static str; 16] = [
+    "ax", "cx", "dx", "bx", "sp", "bp", "si", "di",
+    "r8w", "r9w", "r10w", "r11w", "r12w", "r13w", "r14w", "r15w"
+];
+static REGS8: [&'static str; 16] = [
+    "al", "cl", "dl", "bl", "spl", "bpl", "sil", "dil",
+    "r8b", "r9b", "r10b", "r11b", "r12b", "r13b", "r14b", "r15b"
+];
+static REGS8_LEGACY: [&'static str; 8] = ["al", "cl", "dl", "bl", "ah", "ch", "dh", "bh"];
+static CONDITIONS: [&'static str; 16] = [
+    "o", "no", "b", "ae", "e", "ne", "be", "a",
+    "s", "ns", "p", "np", "l", "ge", "le", "g"
+];
+static ARITHMETIC: [&'static str; 8] = ["add", "or", "adc", "sbb", "and", "sub", "xor", "cmp"];
+static SHIFTS: [&'static str; 8] = ["rol", "ror", "rcl", "rcr", "shl", "shr", "sal", "sar"];
+static UNARY: [&'static str; 8] = ["test", "test", "not", "neg", "mul", "imul", "div", "idiv"];
+
+/// Return `None` from the current function if the option is `None`
+macro_rules! opt(
+    ($e:expr) => (
+        match $e {
+            Some(v) => v,
+            None => return None
+        }
+    )
+);
+
+/// The size of an operand
+#[derive(Copy, PartialEq)]
+enum Size {
+    Byte,
+    Word,
+    Dword,
+    Qword,
+    Xmm
+}
+impl Size {
+    fn ptr(self) -> &'static str {
+        match self {
+            Size::Byte => "byte ptr ",
+            Size::Word => "word ptr ",
+            Size::Dword => "dword ptr ",
+            Size::Qword => "qword ptr ",
+            Size::Xmm => ""
+        }
+    }
+}
+
+/// Format an immediate as hexadecimal
+fn hex(value: i64) -> String {
+    if value < 0 {
+        format!("-0x{:x}", value.abs())
+    } else {
+        format!("0x{:x}", value)
+    }
+}
+
+struct Decoder<'a> {
+    code: &'a [u8],
+    pos: usize,
+    address: usize,
+    rex: u8,
+    operand_size: bool,
+    repeat: Option<u8>
+}
+impl<'a> Decoder<'a> {
+    fn byte(&mut self) -> Option<u8> {
+        let byte = self.code.get(self.pos).map(|&byte| byte);
+        self.pos += 1;
+        byte
+    }
+    fn imm8(&mut self) -> Option<i64> {
+        self.byte().map(|byte| byte as i8 as i64)
+    }
+    fn imm16(&mut self) -> Option<i64> {
+        let low = opt!(self.byte()) as u16;
+        let high = opt!(self.byte()) as u16;
+        Some((low | high << 8) as i16 as i64)
+    }
+    fn imm32(&mut self) -> Option<i64> {
+        let mut value = 0u32;
+        for shift in range(0, 4) {
+            value |= (opt!(self.byte()) as u32) << (shift * 8);
+        }
+        Some(value as i32 as i64)
+    }
+    fn imm64(&mut self) -> Option<i64> {
+        let low = opt!(self.imm32()) as u32 as u64;
+        let high = opt!(self.imm32()) as u32 as u64;
+        Some((low | high << 32) as i64)
+    }
+    /// Read an immediate of the operand size, which is never more than 32 bits
+    fn imm(&mut self, size: Size) -> Option<i64> {
+        match size {
+            Size::Byte => self.imm8(),
+            Size::Word => self.imm16(),
+            _ => self.imm32()
+        }
+    }
+    /// The branch target of a relative displacement
+    fn target(&self, rel: i64) -> String {
+        format!("0x{:x}", (self.address + self.pos) as i64 + rel)
+    }
+    fn wide(&self) -> bool {
+        self.rex & 8 != 0
+    }
+    /// The size of operands that aren't bytes
+    fn size(&self) -> Size {
+        if self.wide() {
+            Size::Qword
+        } else if self.operand_size {
+            Size::Word
+        } else {
+            Size::Dword
+        }
+    }
+    fn reg(&self, size: Size, num: u8) -> String {
+        let num = num as usize;
+        match size {
+            Size::Byte if self.rex == 0 && num < 8 => REGS8_LEGACY[num].to_string(),
+            Size::Byte => REGS8[num].to_string(),
+            Size::Word => REGS16[num].to_string(),
+            Size::Dword => REGS32[num].to_string(),
+            Size::Qword => REGS64[num].to_string(),
+            Size::Xmm => format!("xmm{}", num)
+        }
+    }
+    /// Decode a ModR/M byte, returning the register field and the register
+    /// or memory operand
+    fn modrm(&mut self, size: Size) -> Option<(u8, String)> {
+        let modrm = opt!(self.byte());
+        let mode = modrm >> 6;
+        let reg = (modrm >> 3 & 7) | (self.rex & 4) << 1;
+        let rm = modrm & 7;
+        if mode == 3 {
+            return Some((reg, self.reg(size, rm | (self.rex & 1) << 3)))
+        }
+        let mut parts = Vec::new();
+        if rm == 4 {
+            let sib = opt!(self.byte());
+            let index = (sib >> 3 & 7) | (self.rex & 2) << 2;
+            let base = sib & 7;
+            if base == 5 && mode == 0 {
+                if index != 4 {
+                    parts.push(format!("{}*{}", REGS64[index as usize], 1 << (sib >> 6)));
+                }
+                parts.push(hex(opt!(self.imm32())));
+                return Some((reg, format!("{}[{}]", size.ptr(), parts.connect(" + "))))
+            }
+            parts.push(REGS64[(base | (self.rex & 1) << 3) as usize].to_string());
+            if index != 4 {
+                parts.push(format!("{}*{}", REGS64[index as usize], 1 << (sib >> 6)));
+            }
+        } else if rm == 5 && mode == 0 {
+            parts.push("rip".to_string());
+            parts.push(hex(opt!(self.imm32())));
+            return Some((reg, format!("{}[{}]", size.ptr(), parts.connect(" + "))))
+        } else {
+            parts.push(REGS64[(rm | (self.rex & 1) << 3) as usize].to_string());
+        }
+        let disp = match mode {
+            1 => opt!(self.imm8()),
+            2 => opt!(self.imm32()),
+            _ => 0
+        };
+        let mut operand = parts.connect(" + ");
+        if disp != 0 {
+            operand.push_str(&*format!(" {} 0x{:x}", if disp < 0 { "-" } else { "+" }, disp.abs()));
+        }
+        Some((reg, format!("{}[{}]", size.ptr(), operand)))
+    }
+    /// Decode `name reg, r/m`
+    fn reg_rm(&mut self, name: &str, reg_size: Size, rm_size: Size) -> Option<String> {
+        let (reg, rm) = opt!(self.modrm(rm_size));
+        Some(format!("{} {}, {}", name, self.reg(reg_size, reg), rm))
+    }
+    /// Decode `name r/m, reg`
+    fn rm_reg(&mut self, name: &str, size: Size) -> Option<String> {
+        let (reg, rm) = opt!(self.modrm(size));
+        Some(format!("{} {}, {}", name, rm, self.reg(size, reg)))
+    }
+    /// The suffix of a scalar or packed SSE instruction for the prefixes
+    fn sse_suffix(&self) -> &'static str {
+        match (self.repeat, self.operand_size) {
+            (Some(0xF3), _) => "ss",
+            (Some(0xF2), _) => "sd",
+            (None, true) => "pd",
+            _ => "ps"
+        }
+    }
+    fn decode(&mut self) -> Option<String> {
+        let mut lock = false;
+        let mut op;
+        loop {
+            op = opt!(self.byte());
+            match op {
+                0x66 => self.operand_size = true,
+                0xF2 | 0xF3 => self.repeat = Some(op),
+                0xF0 => lock = true,
+                0x26 | 0x2E | 0x36 | 0x3E | 0x64 | 0x65 => (),
+                0x40...0x4F => {
+                    self.rex = op;
+                    op = opt!(self.byte());
+                    break
+                },
+                _ => break
+            }
+        }
+        let text = opt!(self.decode_op(op));
+        Some(if lock { format!("lock {}", text) } else { text })
+    }
+    fn decode_op(&mut self, op: u8) -> Option<String> {
+        let size = self.size();
+        let low = op & 7;
+        Some(match op {
+            0x00...0x3F if low < 6 => {
+                let name = ARITHMETIC[(op >> 3) as usize];
+                match low {
+                    0 => opt!(self.rm_reg(name, Size::Byte)),
+                    1 => opt!(self.rm_reg(name, size)),
+                    2 => opt!(self.reg_rm(name, Size::Byte, Size::Byte)),
+                    3 => opt!(self.reg_rm(name, size, size)),
+                    4 => format!("{} al, {}", name, hex(opt!(self.imm8()))),
+                    _ => {
+                        let imm = opt!(self.imm(size));
+                        format!("{} {}, {}", name, self.reg(size, 0), hex(imm))
+                    }
+                }
+            },
+            0x50...0x57 => format!("push {}", REGS64[(op - 0x50 | (self.rex & 1) << 3) as usize]),
+            0x58...0x5F => format!("pop {}", REGS64[(op - 0x58 | (self.rex & 1) << 3) as usize]),
+            0x63 => opt!(self.reg_rm("movsxd", size, Size::Dword)),
+            0x68 => format!("push {}", hex(opt!(self.imm32()))),
+            0x6A => format!("push {}", hex(opt!(self.imm8()))),
+            0x69 | 0x6B => {
+                let (reg, rm) = opt!(self.modrm(size));
+                let imm = if op == 0x69 { opt!(self.imm(size)) } else { opt!(self.imm8()) };
+                format!("imul {}, {}, {}", self.reg(size, reg), rm, hex(imm))
+            },
+            0x70...0x7F => {
+                let rel = opt!(self.imm8());
+                format!("j{} {}", CONDITIONS[(op - 0x70) as usize], self.target(rel))
+            },
+            0x80 | 0x81 | 0x83 => {
+                let op_size = if op == 0x80 { Size::Byte } else { size };
+                let (reg, rm) = opt!(self.modrm(op_size));
+                let imm = if op == 0x81 { opt!(self.imm(size)) } else { opt!(self.imm8()) };
+                format!("{} {}, {}", ARITHMETIC[(reg & 7) as usize], rm, hex(imm))
+            },
+            0x84 => opt!(self.rm_reg("test", Size::Byte)),
+            0x85 => opt!(self.rm_reg("test", size)),
+            0x86 => opt!(self.rm_reg("xchg", Size::Byte)),
+            0x87 => opt!(self.rm_reg("xchg", size)),
+            0x88 => opt!(self.rm_reg("mov", Size::Byte)),
+            0x89 => opt!(self.rm_reg("mov", size)),
+            0x8A => opt!(self.reg_rm("mov", Size::Byte, Size::Byte)),
+            0x8B => opt!(self.reg_rm("mov", size, size)),
+            0x8D => opt!(self.reg_rm("lea", size, Size::Xmm)),
+            0x90 if self.rex & 1 == 0 => "nop".to_string(),
+            0x91...0x97 => format!("xchg {}, {}", self.reg(size, op - 0x90 | (self.rex & 1) << 3), self.reg(size, 0)),
+            0x98 => match size {
+                Size::Qword => "cdqe",
+                Size::Word => "cbw",
+                _ => "cwde"
+            }.to_string(),
+            0x99 => match size {
+                Size::Qword => "cqo",
+                Size::Word => "cwd",
+                _ => "cdq"
+            }.to_string(),
+            0xA4 | 0xA5 | 0xAA | 0xAB => {
+                let name = if op < 0xA8 { "movs" } else { "stos" };
+                let suffix = if op & 1 == 0 {
+                    "b"
+                } else {
+                    match size {
+                        Size::Qword => "q",
+                        Size::Word => "w",
+                        _ => "d"
+                    }
+                };
+                let prefix = if self.repeat.is_some() { "rep " } else { "" };
+                format!("{}{}{}", prefix, name, suffix)
+            },
+            0xB0...0xB7 => {
+                let reg = self.reg(Size::Byte, op - 0xB0 | (self.rex & 1) << 3);
+                format!("mov {}, {}", reg, hex(opt!(self.imm8())))
+            },
+            0xB8...0xBF => {
+                let reg = self.reg(size, op - 0xB8 | (self.rex & 1) << 3);
+                let imm = if self.wide() { opt!(self.imm64()) } else { opt!(self.imm(size)) };
+                format!("mov {}, {}", reg, hex(imm))
+            },
+            0xC0 | 0xC1 | 0xD0...0xD3 => {
+                let op_size = if op & 1 == 0 { Size::Byte } else { size };
+                let (reg, rm) = opt!(self.modrm(op_size));
+                let count = match op {
+                    0xC0 | 0xC1 => hex(opt!(self.imm8())),
+                    0xD0 | 0xD1 => "1".to_string(),
+                    _ => "cl".to_string()
+                };
+                format!("{} {}, {}", SHIFTS[(reg & 7) as usize], rm, count)
+            },
+            0xC2 => format!("ret {}", hex(opt!(self.imm16()) & 0xFFFF)),
+            0xC3 => "ret".to_string(),
+            0xC6 | 0xC7 => {
+                let op_size = if op == 0xC6 { Size::Byte } else { size };
+                let (_, rm) = opt!(self.modrm(op_size));
+                format!("mov {}, {}", rm, hex(opt!(self.imm(op_size))))
+            },
+            0xC9 => "leave".to_string(),
+            0xCC => "int3".to_string(),
+            0xE8 => {
+                let rel = opt!(self.imm32());
+                format!("call {}", self.target(rel))
+            },
+            0xE9 => {
+                let rel = opt!(self.imm32());
+                format!("jmp {}", self.target(rel))
+            },
+            0xEB => {
+                let rel = opt!(self.imm8());
+                format!("jmp {}", self.target(rel))
+            },
+            0xF4 => "hlt".to_string(),
+            0xF6 | 0xF7 => {
+                let op_size = if op == 0xF6 { Size::Byte } else { size };
+                let (reg, rm) = opt!(self.modrm(op_size));
+                let reg = reg & 7;
+                if reg < 2 {
+                    format!("test {}, {}", rm, hex(opt!(self.imm(op_size))))
+                } else {
+                    format!("{} {}", UNARY[reg as usize], rm)
+                }
+            },
+            0xFE => {
+                let (reg, rm) = opt!(self.modrm(Size::Byte));
+                match reg & 7 {
+                    0 => format!("inc {}", rm),
+                    1 => format!("dec {}", rm),
+                    _ => return None
+                }
+            },
+            0xFF => {
+                let reg = opt!(self.code.get(self.pos).map(|&byte| byte >> 3 & 7));
+                let op_size = if reg >= 2 { Size::Qword } else { size };
+                let (_, rm) = opt!(self.modrm(op_size));
+                match reg {
+                    0 => format!("inc {}", rm),
+                    1 => format!("dec {}", rm),
+                    2 => format!("call {}", rm),
+                    4 => format!("jmp {}", rm),
+                    6 => format!("push {}", rm),
+                    _ => return None
+                }
+            },
+            0x0F => return self.decode_0f(),
+            _ => return None
+        })
+    }
+    /// Decode an instruction whose opcode starts with 0x0F
+    fn decode_0f(&mut self) -> Option<String> {
+        let op = opt!(self.byte());
+        let size = self.size();
+        Some(match op {
+            0x05 => "syscall".to_string(),
+            0x0B => "ud2".to_string(),
+            0x1F => {
+                let (_, rm) = opt!(self.modrm(size));
+                format!("nop {}", rm)
+            },
+            0x10 | 0x11 => {
+                let name = format!("mov{}", match self.sse_suffix() {
+                    "ps" => "ups",
+                    "pd" => "upd",
+                    suffix => suffix
+                });
+                if op == 0x10 {
+                    opt!(self.reg_rm(&*name, Size::Xmm, Size::Xmm))
+                } else {
+                    opt!(self.rm_reg(&*name, Size::Xmm))
+                }
+            },
+            0x28 | 0x29 => {
+                let name = if self.operand_size { "movapd" } else { "movaps" };
+                if op == 0x28 {
+                    opt!(self.reg_rm(name, Size::Xmm, Size::Xmm))
+                } else {
+                    opt!(self.rm_reg(name, Size::Xmm))
+                }
+            },
+            0x2A => {
+                let name = format!("cvtsi2{}", &self.sse_suffix()[1..]);
+                let rm_size = if self.wide() { Size::Qword } else { Size::Dword };
+                opt!(self.reg_rm(&*name, Size::Xmm, rm_size))
+            },
+            0x2C | 0x2D => {
+                let truncate = if op == 0x2C { "t" } else { "" };
+                let name = format!("cvt{}{}2si", truncate, self.sse_suffix());
+                let reg_size = if self.wide() { Size::Qword } else { Size::Dword };
+                opt!(self.reg_rm(&*name, reg_size, Size::Xmm))
+            },
+            0x2E | 0x2F => {
+                let name = format!("{}comis{}", if op == 0x2E { "u" } else { "" },
+                    if self.operand_size { "d" } else { "s" });
+                opt!(self.reg_rm(&*name, Size::Xmm, Size::Xmm))
+            },
+            0x40...0x4F => {
+                let name = format!("cmov{}", CONDITIONS[(op - 0x40) as usize]);
+                opt!(self.reg_rm(&*name, size, size))
+            },
+            0x51 | 0x54 | 0x57 | 0x58 | 0x59 | 0x5C...0x5F => {
+                let name = match op {
+                    0x51 => "sqrt",
+                    0x54 => "and",
+                    0x57 => "xor",
+                    0x58 => "add",
+                    0x59 => "mul",
+                    0x5C => "sub",
+                    0x5D => "min",
+                    0x5E => "div",
+                    _ => "max"
+                };
+                let name = format!("{}{}", name, self.sse_suffix());
+                opt!(self.reg_rm(&*name, Size::Xmm, Size::Xmm))
+            },
+            0x5A => {
+                let name = match self.sse_suffix() {
+                    "ss" => "cvtss2sd",
+                    "sd" => "cvtsd2ss",
+                    "pd" => "cvtpd2ps",
+                    _ => "cvtps2pd"
+                };
+                opt!(self.reg_rm(name, Size::Xmm, Size::Xmm))
+            },
+            0x6E if self.operand_size => {
+                let name = if self.wide() { "movq" } else { "movd" };
+                let rm_size = if self.wide() { Size::Qword } else { Size::Dword };
+                opt!(self.reg_rm(name, Size::Xmm, rm_size))
+            },
+            0x7E if self.repeat == Some(0xF3) => opt!(self.reg_rm("movq", Size::Xmm, Size::Xmm)),
+            0x7E if self.operand_size => {
+                let name = if self.wide() { "movq" } else { "movd" };
+                let rm_size = if self.wide() { Size::Qword } else { Size::Dword };
+                let (reg, rm) = opt!(self.modrm(rm_size));
+                format!("{} {}, {}", name, rm, self.reg(Size::Xmm, reg))
+            },
+            0x80...0x8F => {
+                let rel = opt!(self.imm32());
+                format!("j{} {}", CONDITIONS[(op - 0x80) as usize], self.target(rel))
+            },
+            0x90...0x9F => {
+                let (_, rm) = opt!(self.modrm(Size::Byte));
+                format!("set{} {}", CONDITIONS[(op - 0x90) as usize], rm)
+            },
+            0xA2 => "cpuid".to_string(),
+            0xAF => opt!(self.reg_rm("imul", size, size)),
+            0xB6 => opt!(self.reg_rm("movzx", size, Size::Byte)),
+            0xB7 => opt!(self.reg_rm("movzx", size, Size::Word)),
+            0xBE => opt!(self.reg_rm("movsx", size, Size::Byte)),
+            0xBF => opt!(self.reg_rm("movsx", size, Size::Word)),
+            0xD6 if self.operand_size => opt!(self.rm_reg("movq", Size::Xmm)),
+            _ => return None
+        })
+    }
+}
+
+/// Decode the instruction at the start of the code given, which is at the
+/// address given, returning its length and its text in Intel syntax
+pub fn decode(code: &[u8], address: usize) -> (usize, String) {
+    let mut decoder = Decoder {
+        code: code,
+        pos: 0,
+        address: address,
+        rex: 0,
+        operand_size: false,
+        repeat: None
+    };
+    match decoder.decode() {
+        Some(text) if decoder.pos <= code.len() => (decoder.pos, text),
+        _ => (1, format!("(bad) 0x{:02x}", code[0]))
+    }
+}
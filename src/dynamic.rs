@@ -0,0 +1,235 @@
+use raw::*;
+use exception::JitException;
+use function::{CompiledFunction, Function};
+use libc::{c_uint, c_void};
+use std::error::Error;
+use std::{fmt, iter, slice};
+use types::{consts, kind, StaticType, TypeRef};
+use util::{from_ptr, NativeRef};
+/// A value whose type is only known at runtime, so it can be passed to and
+/// from functions whose signatures are built at runtime
+#[derive(Clone, Debug, PartialEq)]
+pub enum DynValue {
+    /// No value
+    Void,
+    /// A signed byte
+    SByte(i8),
+    /// An unsigned byte
+    UByte(u8),
+    /// A signed short
+    Short(i16),
+    /// An unsigned short
+    UShort(u16),
+    /// A signed integer
+    Int(i32),
+    /// An unsigned integer
+    UInt(u32),
+    /// A signed native integer
+    NInt(isize),
+    /// An unsigned native integer
+    NUInt(usize),
+    /// A signed long
+    Long(i64),
+    /// An unsigned long
+    ULong(u64),
+    /// A 32-bit float
+    Float32(f32),
+    /// A 64-bit float
+    Float64(f64),
+    /// A native float, as its raw bytes, since it can be more precise than
+    /// any Rust float type
+    NFloat(Vec<u8>),
+    /// A pointer
+    Pointer(*mut c_void),
+    /// A struct or union, as its raw bytes
+    Struct(Vec<u8>)
+}
+impl DynValue {
+    /// Get the type of this value, or `None` if it is a struct
+    pub fn get_type(&self) -> Option<StaticType> {
+        Some(match *self {
+            DynValue::Void => consts::get_void(),
+            DynValue::SByte(_) => consts::get_sbyte(),
+            DynValue::UByte(_) => consts::get_ubyte(),
+            DynValue::Short(_) => consts::get_short(),
+            DynValue::UShort(_) => consts::get_ushort(),
+            DynValue::Int(_) => consts::get_int(),
+            DynValue::UInt(_) => consts::get_uint(),
+            DynValue::NInt(_) => consts::get_nint(),
+            DynValue::NUInt(_) => consts::get_nuint(),
+            DynValue::Long(_) => consts::get_long(),
+            DynValue::ULong(_) => consts::get_ulong(),
+            DynValue::Float32(_) => consts::get_float32(),
+            DynValue::Float64(_) => consts::get_float64(),
+            DynValue::NFloat(_) => consts::get_nfloat(),
+            DynValue::Pointer(_) => consts::get_void_ptr(),
+            DynValue::Struct(_) => return None
+        })
+    }
+    /// Check if this value can be passed where a value of the type given is
+    /// expected, which needs the type to be of exactly the same kind
+    pub fn fits(&self, ty: TypeRef) -> bool {
+        let ty:TypeRef = unsafe { from_ptr(jit_type_remove_tags(ty.as_ptr())) };
+        let kind = ty.get_kind();
+        match *self {
+            DynValue::Struct(ref bytes) =>
+                (ty.is_struct() || ty.is_union()) && ty.get_size() == bytes.len(),
+            DynValue::NFloat(ref bytes) =>
+                kind == kind::NFloat && ty.get_size() == bytes.len(),
+            DynValue::Pointer(_) =>
+                kind == kind::Pointer || kind == kind::Signature,
+            _ => kind == self.get_type().unwrap().get_kind()
+        }
+    }
+    /// Read a value of the type given from the pointer given
+    pub unsafe fn read(ty: TypeRef, ptr: *const c_void) -> DynValue {
+        let ty:TypeRef = from_ptr(jit_type_remove_tags(ty.as_ptr()));
+        let kind = ty.get_kind();
+        if kind == kind::Void {
+            DynValue::Void
+        } else if kind == kind::SByte {
+            DynValue::SByte(*(ptr as *const i8))
+        } else if kind == kind::UByte {
+            DynValue::UByte(*(ptr as *const u8))
+        } else if kind == kind::Short {
+            DynValue::Short(*(ptr as *const i16))
+        } else if kind == kind::UShort {
+            DynValue::UShort(*(ptr as *const u16))
+        } else if kind == kind::Int {
+            DynValue::Int(*(ptr as *const i32))
+        } else if kind == kind::UInt {
+            DynValue::UInt(*(ptr as *const u32))
+        } else if kind == kind::NInt {
+            DynValue::NInt(*(ptr as *const isize))
+        } else if kind == kind::NUInt {
+            DynValue::NUInt(*(ptr as *const usize))
+        } else if kind == kind::Long {
+            DynValue::Long(*(ptr as *const i64))
+        } else if kind == kind::ULong {
+            DynValue::ULong(*(ptr as *const u64))
+        } else if kind == kind::Float32 {
+            DynValue::Float32(*(ptr as *const f32))
+        } else if kind == kind::Float64 {
+            DynValue::Float64(*(ptr as *const f64))
+        } else if kind == kind::NFloat {
+            DynValue::NFloat(slice::from_raw_parts(ptr as *const u8, ty.get_size()).to_vec())
+        } else if kind == kind::Pointer || kind == kind::Signature {
+            DynValue::Pointer(*(ptr as *const *mut c_void))
+        } else {
+            DynValue::Struct(slice::from_raw_parts(ptr as *const u8, ty.get_size()).to_vec())
+        }
+    }
+    /// Write this value to the pointer given
+    pub unsafe fn write(&self, ptr: *mut c_void) {
+        match *self {
+            DynValue::Void => (),
+            DynValue::SByte(v) => *(ptr as *mut i8) = v,
+            DynValue::UByte(v) => *(ptr as *mut u8) = v,
+            DynValue::Short(v) => *(ptr as *mut i16) = v,
+            DynValue::UShort(v) => *(ptr as *mut u16) = v,
+            DynValue::Int(v) => *(ptr as *mut i32) = v,
+            DynValue::UInt(v) => *(ptr as *mut u32) = v,
+            DynValue::NInt(v) => *(ptr as *mut isize) = v,
+            DynValue::NUInt(v) => *(ptr as *mut usize) = v,
+            DynValue::Long(v) => *(ptr as *mut i64) = v,
+            DynValue::ULong(v) => *(ptr as *mut u64) = v,
+            DynValue::Float32(v) => *(ptr as *mut f32) = v,
+            DynValue::Float64(v) => *(ptr as *mut f64) = v,
+            DynValue::Pointer(v) => *(ptr as *mut *mut c_void) = v,
+            DynValue::NFloat(ref bytes) | DynValue::Struct(ref bytes) => {
+                jit_memcpy(ptr, bytes.as_ptr() as *const c_void, bytes.len() as c_uint);
+            }
+        }
+    }
+}
+/// An error from applying a function to dynamic arguments
+#[derive(Debug)]
+pub enum ApplyError {
+    /// The wrong number of arguments was given
+    ArgCount {
+        /// The number of arguments the function takes
+        expected: usize,
+        /// The number of arguments that were given
+        found: usize
+    },
+    /// The argument at this index doesn't fit the type of its parameter
+    ArgType(usize),
+    /// The function threw an exception
+    Exception(JitException)
+}
+impl fmt::Display for ApplyError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ApplyError::ArgCount { expected, found } =>
+                write!(fmt, "expected {} arguments, found {}", expected, found),
+            ApplyError::ArgType(index) =>
+                write!(fmt, "argument {} has the wrong type", index),
+            ApplyError::Exception(ref exception) => exception.fmt(fmt)
+        }
+    }
+}
+impl Error for ApplyError {
+    fn description(&self) -> &str {
+        match *self {
+            ApplyError::ArgCount { .. } => "Wrong number of arguments",
+            ApplyError::ArgType(_) => "Argument has the wrong type",
+            ApplyError::Exception(ref exception) => exception.description()
+        }
+    }
+}
+/// Make a buffer big and aligned enough to hold a value of the size given
+fn new_buffer(size: usize) -> Vec<u64> {
+    iter::repeat(0u64).take((size + 7) / 8).collect()
+}
+/// Write the arguments into buffers laid out as the signature's parameters
+fn marshal(signature: TypeRef, args: &[DynValue]) -> Result<Vec<Vec<u64>>, ApplyError> {
+    let params = signature.params().collect::<Vec<_>>();
+    if params.len() != args.len() {
+        return Err(ApplyError::ArgCount {
+            expected: params.len(),
+            found: args.len()
+        })
+    }
+    let mut buffers = Vec::with_capacity(args.len());
+    for (index, (param, arg)) in params.iter().zip(args.iter()).enumerate() {
+        if !arg.fits(**param) {
+            return Err(ApplyError::ArgType(index))
+        }
+        let mut buffer = new_buffer(param.get_size());
+        unsafe {
+            arg.write(buffer.as_mut_ptr() as *mut c_void);
+        }
+        buffers.push(buffer);
+    }
+    Ok(buffers)
+}
+/// Call the native function given, which has the signature given, with some
+/// dynamic arguments
+///
+/// This is unsafe because the function must actually have the signature given
+pub unsafe fn apply(signature: TypeRef, func: *mut c_void, args: &[DynValue]) -> Result<DynValue, ApplyError> {
+    let mut buffers = try!(marshal(signature, args));
+    let mut pointers = buffers.iter_mut().map(|buffer| buffer.as_mut_ptr() as *mut c_void).collect::<Vec<_>>();
+    let ret_type = signature.get_return().unwrap_or(consts::get_void());
+    let mut ret = new_buffer(ret_type.get_size());
+    jit_apply(signature.as_ptr(), func, pointers.as_mut_ptr(), pointers.len() as c_uint, ret.as_mut_ptr() as *mut c_void);
+    Ok(DynValue::read(ret_type, ret.as_ptr() as *const c_void))
+}
+impl<'a> CompiledFunction<'a> {
+    /// Call this function with some dynamic arguments, checking them against
+    /// its signature. Any exception thrown while it runs is returned as an
+    /// error.
+    pub fn apply_dyn(&self, args: &[DynValue]) -> Result<DynValue, ApplyError> {
+        let signature = self.get_signature();
+        let mut buffers = try!(marshal(signature, args));
+        let mut pointers = buffers.iter_mut().map(|buffer| buffer.as_mut_ptr() as *mut c_void).collect::<Vec<_>>();
+        let ret_type = signature.get_return().unwrap_or(consts::get_void());
+        let mut ret = new_buffer(ret_type.get_size());
+        unsafe {
+            match self.apply(pointers.as_mut_slice(), ret.as_mut_ptr() as *mut c_void) {
+                Ok(()) => Ok(DynValue::read(ret_type, ret.as_ptr() as *const c_void)),
+                Err(exception) => Err(ApplyError::Exception(exception))
+            }
+        }
+    }
+}
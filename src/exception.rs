@@ -0,0 +1,214 @@
+use raw::*;
+use libc::{c_int, c_void};
+use std::cell::RefCell;
+use std::error::Error;
+use std::{fmt, ptr};
+use trace::StackTrace;
+/// An exception that LibJIT throws by itself when something goes wrong
+#[repr(i32)]
+#[derive(Copy, Debug, Eq, PartialEq)]
+pub enum BuiltinException {
+    /// An arithmetic operation overflowed
+    Overflow = 0,
+    /// An arithmetic operation failed
+    Arithmetic = -1,
+    /// A number was divided by zero
+    DivisionByZero = -2,
+    /// A function could not be compiled
+    CompileError = -3,
+    /// There was not enough memory to continue
+    OutOfMemory = -4,
+    /// A null pointer was dereferenced
+    NullReference = -5,
+    /// A null function pointer was called
+    NullFunction = -6,
+    /// A nested function was called from a non-nested context
+    CalledNested = -7,
+    /// An array index was out of bounds
+    OutOfBounds = -8,
+    /// A branch was made to a label that was never defined
+    UndefinedLabel = -9,
+    /// The code cache is full
+    MemoryFull = -10000
+}
+static BUILTINS: [BuiltinException; 11] = [
+    BuiltinException::Overflow,
+    BuiltinException::Arithmetic,
+    BuiltinException::DivisionByZero,
+    BuiltinException::CompileError,
+    BuiltinException::OutOfMemory,
+    BuiltinException::NullReference,
+    BuiltinException::NullFunction,
+    BuiltinException::CalledNested,
+    BuiltinException::OutOfBounds,
+    BuiltinException::UndefinedLabel,
+    BuiltinException::MemoryFull
+];
+/// The objects thrown for builtin exceptions, so they can be told apart from
+/// objects thrown by generated code
+static BUILTIN_OBJECTS: [u8; 11] = [0; 11];
+impl BuiltinException {
+    /// Get the builtin exception that the code given represents
+    pub fn from_code(code: c_int) -> Option<BuiltinException> {
+        BUILTINS.iter().find(|e| **e as c_int == code).map(|e| *e)
+    }
+    /// Get the object that is thrown for this exception
+    pub fn as_object(self) -> *mut c_void {
+        let index = BUILTINS.iter().position(|e| *e == self).unwrap();
+        &BUILTIN_OBJECTS[index] as *const u8 as *mut c_void
+    }
+}
+impl fmt::Display for BuiltinException {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}", self.description())
+    }
+}
+impl Error for BuiltinException {
+    fn description(&self) -> &'static str {
+        match *self {
+            BuiltinException::Overflow => "Overflow during checked arithmetic operation",
+            BuiltinException::Arithmetic => "Arithmetic exception (dividing the minimum integer by -1)",
+            BuiltinException::DivisionByZero => "Division by zero",
+            BuiltinException::CompileError => "Error during function compilation",
+            BuiltinException::OutOfMemory => "Out of memory",
+            BuiltinException::NullReference => "Null pointer dereferenced",
+            BuiltinException::NullFunction => "Null function pointer called",
+            BuiltinException::CalledNested => "Nested function called from non-nested context",
+            BuiltinException::OutOfBounds => "Array index out of bounds",
+            BuiltinException::UndefinedLabel => "Undefined label",
+            BuiltinException::MemoryFull => "Code cache is full"
+        }
+    }
+}
+/// An exception that was thrown while running a compiled function
+#[derive(Copy, Debug, PartialEq)]
+pub enum JitException {
+    /// An exception that LibJIT threw by itself
+    Builtin(BuiltinException),
+    /// An object that was thrown by `insn_throw` or by a handler
+    Object(*mut c_void)
+}
+impl JitException {
+    /// Work out what exception the thrown object given represents
+    pub fn from_object(object: *mut c_void) -> JitException {
+        let start = BUILTIN_OBJECTS.as_ptr() as usize;
+        let address = object as usize;
+        if address >= start && address < start + BUILTIN_OBJECTS.len() {
+            JitException::Builtin(BUILTINS[address - start])
+        } else {
+            JitException::Object(object)
+        }
+    }
+    /// Get the object that is thrown for this exception
+    pub fn as_object(self) -> *mut c_void {
+        match self {
+            JitException::Builtin(builtin) => builtin.as_object(),
+            JitException::Object(object) => object
+        }
+    }
+}
+impl fmt::Display for JitException {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            JitException::Builtin(builtin) => builtin.fmt(fmt),
+            JitException::Object(object) => write!(fmt, "Exception object thrown at {:?}", object)
+        }
+    }
+}
+impl Error for JitException {
+    fn description(&self) -> &str {
+        match *self {
+            JitException::Builtin(builtin) => builtin.description(),
+            JitException::Object(_) => "Exception object thrown"
+        }
+    }
+}
+
+thread_local!(static HANDLER: RefCell<Option<Box<FnMut(BuiltinException) -> Option<*mut c_void>>>> = RefCell::new(None));
+thread_local!(static LAST_TRACE: RefCell<Option<StackTrace>> = RefCell::new(None));
+
+extern fn handle_builtin(code: c_int) -> *mut c_void {
+    let exception = match BuiltinException::from_code(code) {
+        Some(exception) => exception,
+        None => return ptr::null_mut()
+    };
+    LAST_TRACE.with(|trace| *trace.borrow_mut() = StackTrace::capture());
+    let object = HANDLER.with(|handler| match *handler.borrow_mut() {
+        Some(ref mut handler) => handler(exception),
+        None => None
+    });
+    object.unwrap_or_else(|| exception.as_object())
+}
+/// Make sure builtin exceptions are thrown as objects on this thread instead of
+/// aborting the process
+pub fn install_handler() {
+    unsafe {
+        jit_exception_set_handler(Some(handle_builtin));
+    }
+}
+/// Set the closure that decides which object to throw when a builtin
+/// exception occurs on this thread. If it returns `None`, the exception is
+/// thrown as `JitException::Builtin`.
+pub fn set_handler<F>(handler: F) where F:FnMut(BuiltinException) -> Option<*mut c_void> + 'static {
+    HANDLER.with(|cell| *cell.borrow_mut() = Some(Box::new(handler)));
+    install_handler();
+}
+/// Remove the closure set by `set_handler` on this thread
+pub fn clear_handler() {
+    HANDLER.with(|cell| *cell.borrow_mut() = None);
+}
+/// Get the last exception that was thrown on this thread
+pub fn get_last() -> Option<JitException> {
+    unsafe {
+        let object = jit_exception_get_last();
+        if object.is_null() {
+            None
+        } else {
+            Some(JitException::from_object(object))
+        }
+    }
+}
+/// Get the last exception that was thrown on this thread and clear it
+pub fn get_last_and_clear() -> Option<JitException> {
+    unsafe {
+        let object = jit_exception_get_last_and_clear();
+        if object.is_null() {
+            None
+        } else {
+            Some(JitException::from_object(object))
+        }
+    }
+}
+/// Take the stack trace of the last builtin exception that was thrown on this
+/// thread, if there was one
+pub fn take_stack_trace() -> Option<StackTrace> {
+    LAST_TRACE.with(|trace| trace.borrow_mut().take())
+}
+/// Set the last exception that was thrown on this thread
+pub fn set_last(exception: JitException) {
+    unsafe {
+        jit_exception_set_last(exception.as_object());
+    }
+}
+/// Clear the last exception that was thrown on this thread
+pub fn clear_last() {
+    unsafe {
+        jit_exception_clear_last();
+    }
+}
+/// Throw an exception to the nearest catcher in generated code.
+///
+/// This is unsafe because it jumps over any Rust frames in between without
+/// running their destructors.
+pub unsafe fn throw(exception: JitException) {
+    install_handler();
+    jit_exception_throw(exception.as_object());
+}
+/// Throw a builtin exception to the nearest catcher in generated code.
+///
+/// This is unsafe because it jumps over any Rust frames in between without
+/// running their destructors.
+pub unsafe fn throw_builtin(exception: BuiltinException) {
+    install_handler();
+    jit_exception_builtin(exception as c_int);
+}
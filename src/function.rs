@@ -1,9 +1,17 @@
 use raw::*;
+use alloc::oom;
+use closure::Closure;
+#[cfg(all(feature = "disasm", target_arch = "x86_64"))]
+use disasm;
 use context::Builder;
-use compile::Compile;
+use compile::{Compile, ExternFn};
+use exception::{self, BuiltinException, JitException};
 use label::Label;
-use types::TypeRef;
-use insn::Block;
+use memory;
+use meta;
+use trace;
+use types::{consts, get, Type, TypeRef};
+use insn::{Block, Blocks, Opcode};
 use util::{self, from_ptr, NativeRef};
 use value::Value;
 use libc::{
@@ -12,14 +20,15 @@ use libc::{
     c_void
 };
 use std::default::Default;
+use std::error::{Error, FromError};
 use std::fmt;
 use std::ops::Index;
 use std::marker::ContravariantLifetime;
-use std::{mem, ptr};
+use std::{mem, ptr, slice};
 use std::ffi::CString;
 /// A platform's application binary interface
 #[repr(C)]
-#[derive(Copy)]
+#[derive(Copy, PartialEq)]
 pub enum Abi {
     /// The C application binary interface
     CDecl,
@@ -50,6 +59,57 @@ pub mod flags {
         }
     );
 }
+/// An error from using a function as if it had a different signature
+pub struct SignatureError {
+    /// The signature that was expected
+    pub expected: Type,
+    /// The signature the function actually has
+    pub found: Type
+}
+impl fmt::Display for SignatureError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "expected a function of type {}, found one of type {}", self.expected, self.found)
+    }
+}
+impl fmt::Debug for SignatureError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "SignatureError {{ expected: {}, found: {} }}", self.expected, self.found)
+    }
+}
+impl Error for SignatureError {
+    fn description(&self) -> &str {
+        "Function signature mismatch"
+    }
+}
+/// An error from calling a compiled function
+#[derive(Debug)]
+pub enum CallError {
+    /// The function was called with the wrong signature
+    Signature(SignatureError),
+    /// The function threw an exception
+    Exception(JitException)
+}
+impl fmt::Display for CallError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CallError::Signature(ref error) => error.fmt(fmt),
+            CallError::Exception(ref exception) => exception.fmt(fmt)
+        }
+    }
+}
+impl Error for CallError {
+    fn description(&self) -> &str {
+        match *self {
+            CallError::Signature(ref error) => error.description(),
+            CallError::Exception(ref exception) => exception.description()
+        }
+    }
+}
+impl FromError<SignatureError> for CallError {
+    fn from_error(error: SignatureError) -> CallError {
+        CallError::Signature(error)
+    }
+}
 /// A function that can be compiled or not
 pub trait Function<'a> : NativeRef {
     /// Check if this function is compiled
@@ -58,6 +118,40 @@ pub trait Function<'a> : NativeRef {
     fn get_signature(&self) -> TypeRef<'a> {
         unsafe { from_ptr(jit_function_get_signature(self.as_ptr())) }
     }
+    /// Check if this function is a candidate for recompilation
+    fn is_recompilable(&self) -> bool {
+        unsafe { jit_function_is_recompilable(self.as_ptr()) != 0 }
+    }
+    /// Get the optimization level of this function
+    fn get_optimization_level(&self) -> c_uint {
+        unsafe { jit_function_get_optimization_level(self.as_ptr()) }
+    }
+    /// Get the metadata of the type given that is attached to this function
+    fn get_meta<T>(&self) -> Option<&T> where T:'static {
+        unsafe {
+            let data = meta::with_function_map(self.as_ptr(), |map| map.get::<T>().map(|data| data as *const T));
+            data.map(|data| &*data)
+        }
+    }
+    /// Attach some metadata to this function, replacing any of the same type.
+    /// The old metadata is kept until the context is dropped, since another
+    /// handle to this function could still be borrowing it.
+    fn set_meta<T>(&self, data: Box<T>) where T:'static {
+        unsafe { meta::with_function_map(self.as_ptr(), |map| map.replace(data)) }
+    }
+    /// Detach the metadata of the type given from this function, returning
+    /// true if there was any. It is kept until the context is dropped, since
+    /// another handle to this function could still be borrowing it.
+    fn remove_meta<T>(&self) -> bool where T:'static {
+        unsafe { meta::with_function_map(self.as_ptr(), |map| map.retire::<T>()) }
+    }
+    /// Dump this function the way LibJIT would, disassembling it if it is
+    /// compiled
+    fn dump(&self) -> Result<String, fmt::Error> {
+        util::dump(|fd| unsafe {
+            jit_dump_function(mem::transmute(fd), self.as_ptr(), ptr::null());
+        })
+    }
 }
 /// Any kind of function, compiled or not
 native_ref!(AnyFunction ContravariantLifetime {
@@ -96,16 +190,59 @@ native_ref!(CompiledFunction ContravariantLifetime {
     _func: jit_function_t
 });
 impl<'a> Function<'a> for CompiledFunction<'a> {
-    /// 10/10 would compile again
+    /// Check if this function is compiled yet, which it might not be if it
+    /// is compiled on demand
     fn is_compiled(&self) -> bool {
-        true
+        unsafe { jit_function_is_compiled(self.as_ptr()) != 0 }
     }
 }
+/// Write the signature of a function followed by its blocks, with each
+/// instruction on its own line
+fn write_function(fmt: &mut fmt::Formatter, func: jit_function_t) -> fmt::Result {
+    unsafe {
+        let signature:TypeRef = from_ptr(jit_function_get_signature(func));
+        try!(writeln!(fmt, "function {}", signature));
+        for block in Blocks::new(func) {
+            try!(writeln!(fmt, "L{}:", block.get_label()));
+            for insn in block.iter() {
+                try!(writeln!(fmt, "    {}", insn));
+            }
+        }
+        Ok(())
+    }
+}
+/// Write the machine code of a compiled function as disassembled instructions
+#[cfg(all(feature = "disasm", target_arch = "x86_64"))]
+fn write_code(fmt: &mut fmt::Formatter, func: &CompiledFunction) -> fmt::Result {
+    fmt.write_str(&*func.disassemble())
+}
+/// Write the machine code of a compiled function as rows of hex bytes
+#[cfg(not(all(feature = "disasm", target_arch = "x86_64")))]
+fn write_code(fmt: &mut fmt::Formatter, func: &CompiledFunction) -> fmt::Result {
+    let code = func.native_code();
+    for (index, row) in code.chunks(16).enumerate() {
+        try!(write!(fmt, "    {:x}:", code.as_ptr() as usize + index * 16));
+        for byte in row.iter() {
+            try!(write!(fmt, " {:02x}", byte));
+        }
+        try!(writeln!(fmt, ""));
+    }
+    Ok(())
+}
 impl<'a> fmt::Display for CompiledFunction<'a> {
+    /// Write the signature and machine code of this function, since its
+    /// instructions are freed along with its builder once it is compiled
+    ///
+    /// The machine code is disassembled when the `disasm` feature is enabled
+    /// on x86-64, and written as rows of hex bytes otherwise. It is left out
+    /// when `native_code` is empty.
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmt, "{}", try!(util::dump(|fd| unsafe {
-            jit_dump_function(mem::transmute(fd), self.as_ptr(), ptr::null());
-        })))
+        if !self.is_compiled() {
+            return writeln!(fmt, "function {} (not compiled yet)", self.get_signature())
+        }
+        let code = self.native_code();
+        try!(writeln!(fmt, "function {} compiled to {} bytes at {:p}", self.get_signature(), code.len(), code.as_ptr()));
+        write_code(fmt, self)
     }
 }
 impl<'a> CompiledFunction<'a> {
@@ -115,6 +252,153 @@ impl<'a> CompiledFunction<'a> {
             mem::transmute(jit_function_to_closure(self._func))
         })
     }
+    /// Get the machine code of this function, from its entry point to the end
+    /// of the code the context's memory manager has for it, which is empty if
+    /// it hasn't been compiled yet or if its context wasn't given a memory
+    /// manager by `ContextBuilder::memory_manager`
+    pub fn native_code(&self) -> &[u8] {
+        if !self.is_compiled() {
+            return &[]
+        }
+        unsafe {
+            let ctx = jit_function_get_context(self.as_ptr());
+            let start = jit_function_to_closure(self.as_ptr());
+            match memory::function_end(ctx, start) {
+                Some(end) if end as usize > start as usize =>
+                    slice::from_raw_parts(start as *const u8, end as usize - start as usize),
+                _ => &[]
+            }
+        }
+    }
+    /// Get the offset marked by `insn_mark_offset` for the native program
+    /// counter given, or `None` if the program counter isn't in this function
+    /// or no offset was marked before it
+    pub fn offset_for_pc(&self, pc: *mut c_void) -> Option<u32> {
+        unsafe {
+            trace::bytecode_offset(self.as_ptr(), pc)
+        }
+    }
+    /// Disassemble the machine code of this function, with a label before
+    /// each instruction that starts the code for a new offset marked by
+    /// `insn_mark_offset`
+    #[cfg(all(feature = "disasm", target_arch = "x86_64"))]
+    pub fn disassemble(&self) -> String {
+        let code = self.native_code();
+        let start = code.as_ptr() as usize;
+        let mut text = String::new();
+        let mut last_offset = None;
+        let mut pos = 0;
+        while pos < code.len() {
+            let offset = unsafe { trace::bytecode_offset(self.as_ptr(), (start + pos) as *mut c_void) };
+            if offset.is_some() && offset != last_offset {
+                text.push_str(&*format!("offset {}:\n", offset.unwrap()));
+                last_offset = offset;
+            }
+            let (len, insn) = disasm::decode(&code[pos..], start + pos);
+            text.push_str(&*format!("    {:x}: {}\n", start + pos, insn));
+            pos += len;
+        }
+        text
+    }
+    /// Call the function with pointers to each of its arguments, storing the
+    /// return value at `ret`. Any exception thrown while it runs is caught and
+    /// returned instead of aborting the process.
+    ///
+    /// This is unsafe because the pointers must point to values of the types
+    /// the signature of this function expects.
+    pub unsafe fn apply(&self, args: &mut [*mut c_void], ret: *mut c_void) -> Result<(), JitException> {
+        exception::install_handler();
+        if jit_function_apply(self.as_ptr(), args.as_mut_ptr(), ret) != 0 {
+            Ok(())
+        } else {
+            Err(exception::get_last_and_clear().unwrap_or(JitException::Object(ptr::null_mut())))
+        }
+    }
+    /// Get a native function pointer of the type given to this function, or
+    /// an error if the type doesn't match the function's signature
+    ///
+    /// This is unsafe because the function pointer isn't tied to the context,
+    /// so it must not be called once the context has been dropped.
+    pub unsafe fn get_fn<F>(&self) -> Result<F, SignatureError> where F:ExternFn {
+        try!(self.check_signature::<F>());
+        Ok(ExternFn::from_code(jit_function_to_closure(self.as_ptr())))
+    }
+    /// Call this function as the native function type given with some
+    /// arguments, after checking the type matches the function's signature.
+    /// Any exception thrown while it runs is returned as an error.
+    pub fn call<F>(&self, args: F::Args) -> Result<F::Output, CallError> where F:ExternFn {
+        try!(self.check_signature::<F>());
+        unsafe {
+            <F as ExternFn>::apply(self, args).map_err(CallError::Exception)
+        }
+    }
+    #[inline(always)]
+    /// Stop this function being a candidate for recompilation
+    pub fn clear_recompilable(&self) {
+        unsafe {
+            jit_function_clear_recompilable(self.as_ptr());
+        }
+    }
+    /// Rebuild the body of this function with the closure given and compile
+    /// it again, returning true if it compiled. Callers that go through
+    /// LibJIT, including other compiled functions, run the new code from then
+    /// on.
+    ///
+    /// This panics if the function is not recompilable.
+    pub fn recompile<F>(&self, cb: F) -> bool where F:FnOnce(&UncompiledFunction<'a>) {
+        if !self.is_recompilable() {
+            panic!("The function must be recompilable")
+        }
+        unsafe {
+            let ctx = jit_function_get_context(self.as_ptr());
+            jit_context_build_start(ctx);
+            let func:UncompiledFunction<'a> = from_ptr(self.as_ptr());
+            cb(&func);
+            let compiled = jit_function_compile(self.as_ptr()) != 0;
+            jit_context_build_end(ctx);
+            compiled
+        }
+    }
+    fn check_signature<F>(&self) -> Result<(), SignatureError> where F:ExternFn {
+        let expected = get::<F>();
+        let found = self.get_signature();
+        if expected.get().is_compatible(found) {
+            Ok(())
+        } else {
+            unsafe {
+                Err(SignatureError {
+                    expected: from_ptr(jit_type_copy(expected.get().as_ptr())),
+                    found: from_ptr(jit_type_copy(found.as_ptr()))
+                })
+            }
+        }
+    }
+}
+
+/// The result code for an on-demand compiler that succeeded
+pub static RESULT_OK: c_int = 1;
+/// Marks a function whose catcher block was made by `insn_try`, in its
+/// metadata
+struct UsesCatcher;
+/// The on-demand compiler closure of a function, kept in its metadata
+struct OnDemandFn(Box<FnMut(&UncompiledFunction) + 'static>);
+extern fn compile_on_demand(func: jit_function_t) -> c_int {
+    unsafe {
+        match meta::with_function_map(func, |map| map.remove::<OnDemandFn>()) {
+            Some(mut cb) => {
+                let uncompiled:UncompiledFunction = from_ptr(func);
+                (cb.0)(&uncompiled);
+                if jit_function_compile(func) != 0 {
+                    RESULT_OK
+                } else {
+                    // keep the closure so the next call can try again
+                    meta::with_function_map(func, |map| map.insert(cb));
+                    BuiltinException::CompileError as c_int
+                }
+            },
+            None => BuiltinException::CompileError as c_int
+        }
+    }
 }
 
 #[derive(PartialEq)]
@@ -156,9 +440,7 @@ impl<'a> Function<'a> for UncompiledFunction<'a> {
 }
 impl<'a> fmt::Display for UncompiledFunction<'a> {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmt, "{}", try!(util::dump(|fd| unsafe {
-            jit_dump_function(mem::transmute(fd), self.as_ptr(), ptr::null());
-        })))
+        write_function(fmt, self.as_ptr())
     }
 }
 #[unsafe_destructor]
@@ -261,133 +543,191 @@ impl<'a> UncompiledFunction<'a> {
         }
     }
     #[inline(always)]
-    /// Return from the function with the value given
-    pub fn insn_return(&self, retval: Value<'a>) {
+    /// Mark the instructions that follow as coming from the bytecode or
+    /// source offset given, so a native program counter in them can be mapped
+    /// back to it
+    pub fn insn_mark_offset(&self, offset: u32) {
         unsafe {
-            jit_insn_return(self.as_ptr(), retval.as_ptr());
+            if jit_insn_mark_offset(self.as_ptr(), offset as jit_int) == 0 {
+                oom()
+            }
         }
     }
     #[inline(always)]
-    /// Return from the function
-    pub fn insn_default_return(&self) {
+    /// Mark a point where a debugger can stop the function, described by the
+    /// values given
+    pub fn insn_mark_breakpoint(&self, data1: isize, data2: isize) {
         unsafe {
-            jit_insn_default_return(self.as_ptr());
+            if jit_insn_mark_breakpoint(self.as_ptr(), data1 as jit_nint, data2 as jit_nint) == 0 {
+                oom()
+            }
         }
     }
     #[inline(always)]
-    /// Make an instruction that multiplies the values
-    pub fn insn_mul(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_mul)
-    }
-    #[inline(always)]
-    /// Make an instruction that multiplies the values and throws upon overflow
-    pub fn insn_mul_ovf(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_mul_ovf)
-    }
-    #[inline(always)]
-    /// Make an instruction that adds the values
-    pub fn insn_add(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_add)
-    }
-    #[inline(always)]
-    /// Make an instruction that adds the values and throws upon overflow
-    pub fn insn_add_ovf(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_add_ovf)
-    }
-    #[inline(always)]
-    /// Make an instruction that subtracts the second value from the first
-    pub fn insn_sub(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_sub)
-    }
-    #[inline(always)]
-    /// Make an instruction that subtracts the second value from the first and throws upon overflow
-    pub fn insn_sub_ovf(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_sub_ovf)
-    }
-    #[inline(always)]
-    /// Make an instruction that divides the first number by the second
-    pub fn insn_div(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_div)
-    }
-    #[inline(always)]
-    /// Make an instruction that finds the remainder when the first number is
-    /// divided by the second
-    pub fn insn_rem(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_rem)
+    /// Mark a point where a debugger can stop the function, described by the
+    /// runtime values given
+    pub fn insn_mark_breakpoint_variable(&self, data1: Value<'a>, data2: Value<'a>) {
+        unsafe {
+            if jit_insn_mark_breakpoint_variable(self.as_ptr(), data1.as_ptr(), data2.as_ptr()) == 0 {
+                oom()
+            }
+        }
     }
     #[inline(always)]
-    /// Make an instruction that checks if the first value is lower than or
-    /// equal to the second
-    pub fn insn_leq(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_le)
+    /// Make an instruction that gets the exception that was thrown most recently
+    pub fn insn_thrown_exception(&self) -> Value<'a> {
+        unsafe {
+            from_ptr(jit_insn_thrown_exception(self.as_ptr()))
+        }
     }
     #[inline(always)]
-    /// Make an instruction that checks if the first value is greater than or
-    /// equal to the second
-    pub fn insn_geq(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_ge)
+    /// Start the catcher block of the function, returning the exception that
+    /// was thrown. There should be exactly one catcher block in a function
+    /// that uses a catcher, and every exception thrown inside the function
+    /// will jump here
+    pub fn insn_start_catcher(&self) -> Value<'a> {
+        unsafe {
+            from_ptr(jit_insn_start_catcher(self.as_ptr()))
+        }
     }
     #[inline(always)]
-    /// Make an instruction that checks if the first value is lower than the second
-    pub fn insn_lt(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_lt)
+    /// Make an instruction that branches to a certain label if the program
+    /// counter where the exception was thrown is not between the start and
+    /// end labels given
+    pub fn insn_branch_if_pc_not_in_range(&self, start: &Label<'a>, end: &Label<'a>,
+                                          label: &mut Label<'a>) {
+        unsafe {
+            jit_insn_branch_if_pc_not_in_range(self.as_ptr(), **start, **end, &mut **label);
+        }
     }
     #[inline(always)]
-    /// Make an instruction that checks if the first value is greater than the second
-    pub fn insn_gt(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_gt)
+    /// Make an instruction that throws the exception again if nothing in
+    /// the catcher block handled it
+    pub fn insn_rethrow_unhandled(&self) {
+        unsafe {
+            jit_insn_rethrow_unhandled(self.as_ptr());
+        }
     }
     #[inline(always)]
-    /// Make an instruction that checks if the values are equal
-    pub fn insn_eq(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_eq)
+    /// Start the finally block at the label given
+    pub fn insn_start_finally(&self, label: &mut Label<'a>) {
+        unsafe {
+            jit_insn_start_finally(self.as_ptr(), &mut **label);
+        }
     }
     #[inline(always)]
-    /// Make an instruction that checks if the values are not equal
-    pub fn insn_neq(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_ne)
+    /// Return from the finally block that is currently being built
+    pub fn insn_return_from_finally(&self) {
+        unsafe {
+            jit_insn_return_from_finally(self.as_ptr());
+        }
     }
     #[inline(always)]
-    /// Make an instruction that performs a bitwise and on the two values
-    pub fn insn_and(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_and)
+    /// Make an instruction that calls the finally block at the label given
+    pub fn insn_call_finally(&self, label: &mut Label<'a>) {
+        unsafe {
+            jit_insn_call_finally(self.as_ptr(), &mut **label);
+        }
     }
     #[inline(always)]
-    /// Make an instruction that performs a bitwise or on the two values
-    pub fn insn_or(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_or)
+    /// Start the filter block at the label given, returning the value that
+    /// was passed to the filter
+    pub fn insn_start_filter(&self, label: &mut Label<'a>, ty: TypeRef) -> Value<'a> {
+        unsafe {
+            from_ptr(jit_insn_start_filter(self.as_ptr(), &mut **label, ty.as_ptr()))
+        }
     }
     #[inline(always)]
-    /// Make an instruction that performs a bitwise xor on the two values
-    pub fn insn_xor(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_xor)
+    /// Return from the filter block that is currently being built with the
+    /// value given
+    pub fn insn_return_from_filter(&self, value: Value<'a>) {
+        unsafe {
+            jit_insn_return_from_filter(self.as_ptr(), value.as_ptr());
+        }
     }
     #[inline(always)]
-    /// Make an instruction that performs a bitwise not on the two values
-    pub fn insn_not(&self, value: Value<'a>) -> Value<'a> {
-        self.insn_unop(value, jit_insn_not)
+    /// Make an instruction that calls the filter block at the label given
+    /// with a value, and gets the value it returns
+    pub fn insn_call_filter(&self, label: &mut Label<'a>, value: Value<'a>,
+                            ty: TypeRef) -> Value<'a> {
+        unsafe {
+            from_ptr(jit_insn_call_filter(self.as_ptr(), &mut **label, value.as_ptr(), ty.as_ptr()))
+        }
     }
-    #[inline(always)]
-    /// Make an instruction that performs a left bitwise shift on the first
-    /// value by the second value
-    pub fn insn_shl(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_shl)
+    /// Make instructions to run the try block, then run the catch block
+    /// with the exception if the try block throws one.
+    ///
+    /// Since LibJIT only allows one catcher block per function, this panics
+    /// if it or `insn_try_finally` was already used in this function.
+    pub fn insn_try<T, C>(&self, try_block: T, catch_block: C)
+        where T:FnOnce(), C:FnOnce(Value<'a>) {
+        self.try_catch(try_block, catch_block, None::<fn()>)
+    }
+    /// Make instructions to run the try block, then run the catch block
+    /// with the exception if the try block throws one, then run the finally
+    /// block after either of them.
+    ///
+    /// Since LibJIT only allows one catcher block per function, this panics
+    /// if it or `insn_try` was already used in this function.
+    pub fn insn_try_finally<T, C, F>(&self, try_block: T, catch_block: C, finally_block: F)
+        where T:FnOnce(), C:FnOnce(Value<'a>), F:FnOnce() {
+        self.try_catch(try_block, catch_block, Some(finally_block))
+    }
+    fn try_catch<T, C, F>(&self, try_block: T, catch_block: C, finally_block: Option<F>)
+        where T:FnOnce(), C:FnOnce(Value<'a>), F:FnOnce() {
+        let used = unsafe {
+            meta::with_function_map(self.as_ptr(), |map| {
+                let used = map.get::<UsesCatcher>().is_some();
+                map.insert(Box::new(UsesCatcher));
+                used
+            })
+        };
+        if used {
+            panic!("A function can only have one try block")
+        }
+        let mut start = Label::new(self);
+        let mut end = Label::new(self);
+        let mut unhandled = Label::new(self);
+        let mut finally = Label::new(self);
+        let mut after = Label::new(self);
+        let has_finally = finally_block.is_some();
+        self.insn_uses_catcher();
+        self.insn_label(&mut start);
+        try_block();
+        self.insn_label(&mut end);
+        if has_finally {
+            self.insn_call_finally(&mut finally);
+        }
+        self.insn_branch(&mut after);
+        let exception = self.insn_start_catcher();
+        self.insn_branch_if_pc_not_in_range(&start, &end, &mut unhandled);
+        catch_block(exception);
+        if has_finally {
+            self.insn_call_finally(&mut finally);
+        }
+        self.insn_branch(&mut after);
+        self.insn_label(&mut unhandled);
+        self.insn_rethrow_unhandled();
+        if let Some(finally_block) = finally_block {
+            self.insn_start_finally(&mut finally);
+            finally_block();
+            self.insn_return_from_finally();
+        }
+        self.insn_label(&mut after);
     }
     #[inline(always)]
-    /// Make an instruction that performs a right bitwise shift on the first
-    /// value by the second value
-    pub fn insn_shr(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_shr)
-    }
-    /// Make an instruction that performs a right bitwise shift on the first
-    /// value by the second value
-    pub fn insn_ushr(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_ushr)
+    /// Return from the function with the value given
+    pub fn insn_return(&self, retval: Value<'a>) {
+        unsafe {
+            jit_insn_return(self.as_ptr(), retval.as_ptr());
+        }
     }
     #[inline(always)]
-    /// Make an instruction that performs a bitwise negate on the value
-    pub fn insn_neg(&self, value: Value<'a>) -> Value<'a> {
-        self.insn_unop(value, jit_insn_neg)
+    /// Return from the function
+    pub fn insn_default_return(&self) {
+        unsafe {
+            jit_insn_default_return(self.as_ptr());
+        }
     }
     #[inline(always)]
     /// Make an instruction that duplicates the value given
@@ -399,11 +739,6 @@ impl<'a> UncompiledFunction<'a> {
     }
     #[inline(always)]
     /// Make an instruction that loads a value from a src value
-    pub fn insn_load(&self, src: Value<'a>) -> Value<'a> {
-        self.insn_unop(src, jit_insn_load)
-    }
-    #[inline(always)]
-    /// Make an instruction that loads a value from a src value
     pub fn insn_load_relative(&self, src: Value<'a>, offset: usize, ty:TypeRef) -> Value<'a> {
         unsafe {
             from_ptr(jit_insn_load_relative(
@@ -431,6 +766,73 @@ impl<'a> UncompiledFunction<'a> {
         }
     }
     #[inline(always)]
+    /// Make an instruction that adds a constant byte offset to a pointer
+    pub fn insn_add_relative(&self, value: Value<'a>, offset: usize) -> Value<'a> {
+        unsafe {
+            from_ptr(jit_insn_add_relative(self.as_ptr(), value.as_ptr(), offset as jit_nint))
+        }
+    }
+    #[inline(always)]
+    /// Make an instruction that loads the element at the index given from an
+    /// array of elements of the type given starting at the base address given
+    pub fn insn_load_elem(&self, base_addr: Value<'a>, index: Value<'a>, elem_type: TypeRef) -> Value<'a> {
+        unsafe {
+            from_ptr(jit_insn_load_elem(self.as_ptr(), base_addr.as_ptr(), index.as_ptr(), elem_type.as_ptr()))
+        }
+    }
+    #[inline(always)]
+    /// Make an instruction that gets the address of the element at the index
+    /// given in an array of elements of the type given starting at the base
+    /// address given
+    pub fn insn_load_elem_address(&self, base_addr: Value<'a>, index: Value<'a>, elem_type: TypeRef) -> Value<'a> {
+        unsafe {
+            from_ptr(jit_insn_load_elem_address(self.as_ptr(), base_addr.as_ptr(), index.as_ptr(), elem_type.as_ptr()))
+        }
+    }
+    #[inline(always)]
+    /// Make an instruction that stores a value as the element at the index
+    /// given in an array starting at the base address given. The type of the
+    /// elements is the type of the value.
+    pub fn insn_store_elem(&self, base_addr: Value<'a>, index: Value<'a>, value: Value<'a>) {
+        unsafe {
+            if jit_insn_store_elem(self.as_ptr(), base_addr.as_ptr(), index.as_ptr(), value.as_ptr()) == 0 {
+                oom()
+            }
+        }
+    }
+    /// Make instructions that throw the builtin exception given
+    fn insn_throw_builtin(&self, exception: BuiltinException) {
+        let signature = get::<fn(c_int)>();
+        let code = self.insn_of(&(exception as c_int));
+        let builtin = jit_exception_builtin as *mut c_void;
+        self.insn_call_native(Some("jit_exception_builtin"), builtin, signature.get(), &mut [code], flags::NO_RETURN);
+    }
+    /// Make instructions that throw `OutOfBounds` unless the index given is
+    /// below the length given. The index is compared as unsigned, so negative
+    /// indices are out of bounds too.
+    fn insn_check_bounds(&self, index: Value<'a>, length: Value<'a>) {
+        let nuint = get::<usize>();
+        let index = self.insn_convert(index, nuint.get(), false);
+        let length = self.insn_convert(length, nuint.get(), false);
+        self.insn_if_not(self.insn_lt(index, length), || {
+            self.insn_throw_builtin(BuiltinException::OutOfBounds);
+        });
+    }
+    /// Make instructions that load the element at the index given from an
+    /// array of `length` elements of the type given, throwing `OutOfBounds`
+    /// if the index isn't below the length
+    pub fn insn_load_elem_checked(&self, base_addr: Value<'a>, index: Value<'a>, length: Value<'a>, elem_type: TypeRef) -> Value<'a> {
+        self.insn_check_bounds(index, length);
+        self.insn_load_elem(base_addr, index, elem_type)
+    }
+    /// Make instructions that store a value as the element at the index given
+    /// in an array of `length` elements, throwing `OutOfBounds` if the index
+    /// isn't below the length
+    pub fn insn_store_elem_checked(&self, base_addr: Value<'a>, index: Value<'a>, length: Value<'a>, value: Value<'a>) {
+        self.insn_check_bounds(index, length);
+        self.insn_store_elem(base_addr, index, value)
+    }
+    #[inline(always)]
     /// Make an instruction that sets a label
     pub fn insn_label(&self, label: &mut Label<'a>) {
         unsafe {
@@ -472,145 +874,6 @@ impl<'a> UncompiledFunction<'a> {
             );
         }
     }
-    #[inline(always)]
-    /// Make an instruction that gets the inverse cosine of the number given
-    pub fn insn_acos(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_acos)
-    }
-    #[inline(always)]
-    /// Make an instruction that gets the inverse sine of the number given
-    pub fn insn_asin(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_asin)
-    }
-    #[inline(always)]
-    /// Make an instruction that gets the inverse tangent of the number given
-    pub fn insn_atan(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_atan)
-    }
-    #[inline(always)]
-    /// Make an instruction that gets the inverse tangent of the numbers given
-    pub fn insn_atan2(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_atan2)
-    }
-    #[inline(always)]
-    /// Make an instruction that finds the nearest integer above a number
-    pub fn insn_ceil(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_ceil)
-    }
-    #[inline(always)]
-    /// Make an instruction that gets the consine of the number given
-    pub fn insn_cos(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_cos)
-    }
-    #[inline(always)]
-    /// Make an instruction that gets the hyperbolic consine of the number given
-    pub fn insn_cosh(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_cosh)
-    }
-    #[inline(always)]
-    /// Make an instruction that gets the natural logarithm rased to the power
-    /// of the number
-    pub fn insn_exp(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_exp)
-    }
-    #[inline(always)]
-    /// Make an instruction that finds the nearest integer below a number
-    pub fn insn_floor(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_floor)
-    }
-    #[inline(always)]
-    /// Make an instruction that gets the natural logarithm of the number
-    pub fn insn_log(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_log)
-    }
-    #[inline(always)]
-    /// Make an instruction that gets the base 10 logarithm of the number
-    pub fn insn_log10(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_log10)
-    }
-    #[inline(always)]
-    /// Make an instruction the gets the result of raising the first value to
-    /// the power of the second value
-    pub fn insn_pow(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_pow)
-    }
-    #[inline(always)]
-    /// Make an instruction the gets the result of rounding the value to the
-    /// nearest integer
-    pub fn insn_rint(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_rint)
-    }
-    #[inline(always)]
-    /// Make an instruction the gets the result of rounding the value to the
-    /// nearest integer
-    pub fn insn_round(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_round)
-    }
-    #[inline(always)]
-    /// Make an instruction the gets the sine of the number
-    pub fn insn_sin(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_sin)
-    }
-    #[inline(always)]
-    /// Make an instruction the gets the hyperbolic sine of the number
-    pub fn insn_sinh(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_sinh)
-    }
-    #[inline(always)]
-    /// Make an instruction the gets the square root of a number
-    pub fn insn_sqrt(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_sqrt)
-    }
-    #[inline(always)]
-    /// Make an instruction the gets the tangent of a number
-    pub fn insn_tan(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_tan)
-    }
-    #[inline(always)]
-    /// Make an instruction the gets the hyperbolic tangent of a number
-    pub fn insn_tanh(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_tanh)
-    }
-    #[inline(always)]
-    /// Make an instruction that truncates the value
-    pub fn insn_trunc(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_trunc)
-    }
-    #[inline(always)]
-    /// Make an instruction that checks if the number is NaN
-    pub fn insn_is_nan(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_is_nan)
-    }
-    #[inline(always)]
-    /// Make an instruction that checks if the number is finite
-    pub fn insn_is_finite(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_is_finite)
-    }
-    #[inline(always)]
-    /// Make an instruction that checks if the number is  infinite
-    pub fn insn_is_inf(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_is_inf)
-    }
-    #[inline(always)]
-    /// Make an instruction that gets the absolute value of a number
-    pub fn insn_abs(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_abs)
-    }
-    #[inline(always)]
-    /// Make an instruction that gets the smallest of two numbers
-    pub fn insn_min(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_min)
-    }
-    #[inline(always)]
-    /// Make an instruction that gets the biggest of two numbers
-    pub fn insn_max(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
-        self.insn_binop(v1, v2, jit_insn_max)
-    }
-    #[inline(always)]
-    /// Make an instruction that gets the sign of a number
-    pub fn insn_sign(&self, v: Value<'a>) -> Value<'a>{
-        self.insn_unop(v, jit_insn_sign)
-    }
 
     /// Call the function, which may or may not be translated yet
     pub fn insn_call<F>(&self, name:Option<&str>, func:&F, sig:Option<TypeRef>,
@@ -639,9 +902,12 @@ impl<'a> UncompiledFunction<'a> {
     }
     /// Make an instruction that calls a native function that has the signature
     /// given with some arguments
-    fn insn_call_native(&self, name: Option<&str>,
-                        native_func: *mut c_void, signature: TypeRef,
-                        args: &mut [Value<'a>], flags: flags::CallFlags) -> Value<'a> {
+    ///
+    /// The native function must really have that signature, or the call
+    /// will misbehave when it runs.
+    pub fn insn_call_native(&self, name: Option<&str>,
+                            native_func: *mut c_void, signature: TypeRef,
+                            args: &mut [Value<'a>], flags: flags::CallFlags) -> Value<'a> {
         unsafe {
             let mut native_args:Vec<_> = args.iter()
                 .map(|arg| arg.as_ptr()).collect();
@@ -713,6 +979,51 @@ impl<'a> UncompiledFunction<'a> {
             , flags)
     }
     #[inline(always)]
+    /// Make an instruction that calls the closure given with the arguments
+    /// given and expects a return value.
+    ///
+    /// This panics if the closure was made in a different context, since its
+    /// Rust closure could be freed before this function is.
+    pub fn insn_call_closure(&self, name: Option<&str>,
+                             closure: &Closure<'a>,
+                             args: &mut [Value<'a>],
+                             flags: flags::CallFlags) -> Value<'a> {
+        if unsafe { jit_function_get_context(self.as_ptr()) != closure.get_context().as_ptr() } {
+            panic!("The closure was made in a different context")
+        }
+        self.insn_call_native(name, closure.as_code(), closure.get_signature(), args, flags)
+    }
+    /// Make an instruction that calls the Rust function given with the
+    /// arguments given and expects a return value, working out the signature
+    /// from the type of the function.
+    ///
+    /// The function can take at most six parameters, since `ExternFn` is only
+    /// implemented for `extern fn` types up to that arity. If the arguments
+    /// don't match its parameters, no instruction is made and the error has
+    /// the signature the arguments would need as the one found.
+    pub fn insn_call_rust<F>(&self, name: Option<&str>,
+                             native_func: F,
+                             args: &[Value<'a>],
+                             flags: flags::CallFlags) -> Result<Value<'a>, SignatureError> where F:ExternFn {
+        let signature = get::<F>();
+        let fits = {
+            let params = signature.get().params().collect::<Vec<_>>();
+            params.len() == args.len()
+                && params.iter().zip(args.iter()).all(|(param, arg)| arg.get_type().is_compatible(**param))
+        };
+        if !fits {
+            let ret = signature.get().get_return().unwrap_or(consts::get_void());
+            let arg_types = args.iter().map(|arg| arg.get_type()).collect::<Vec<_>>();
+            let mut arg_types = arg_types.iter().map(|ty| **ty).collect::<Vec<_>>();
+            return Err(SignatureError {
+                expected: unsafe { from_ptr(jit_type_copy(signature.get().as_ptr())) },
+                found: Type::new_signature(signature.get().get_abi(), ret, arg_types.as_mut_slice())
+            })
+        }
+        let mut args = args.to_vec();
+        Ok(self.insn_call_native(name, native_func.as_code(), signature.get(), args.as_mut_slice(), flags))
+    }
+    #[inline(always)]
     /// Make an instruction that copies memory from a source address to a destination address
     pub fn insn_memcpy(&self, dest: Value<'a>, source: Value<'a>, size: Value<'a>) -> bool {
         unsafe {
@@ -748,26 +1059,191 @@ impl<'a> UncompiledFunction<'a> {
         }
     }
     #[inline(always)]
-    fn insn_binop(&self,
-                    v1: Value<'a>, v2: Value<'a>,
-                    f: unsafe extern "C" fn(
-                        jit_function_t,
-                        jit_value_t,
-                        jit_value_t) -> jit_value_t)
-                    -> Value<'a> {
+    /// Make an instruction that gets the address of the label given
+    pub fn insn_address_of_label(&self, label: &mut Label<'a>) -> Value<'a> {
+        unsafe {
+            from_ptr(jit_insn_address_of_label(self.as_ptr(), &mut **label))
+        }
+    }
+    #[inline(always)]
+    /// Start a new block, so the instructions that follow are in it
+    pub fn insn_new_block(&self) {
         unsafe {
-            from_ptr(f(self.as_ptr(), v1.as_ptr(), v2.as_ptr()))
+            if jit_insn_new_block(self.as_ptr()) == 0 {
+                oom()
+            }
         }
     }
     #[inline(always)]
-    fn insn_unop(&self,
-                    value: Value<'a>,
-                    f: unsafe extern "C" fn(
-                        jit_function_t,
-                        jit_value_t) -> jit_value_t)
-                    -> Value<'a> {
+    /// Move the blocks from the first label up to the second label to the
+    /// end of the function
+    pub fn insn_move_blocks_to_end(&self, from: &Label<'a>, to: &Label<'a>) {
         unsafe {
-            from_ptr(f(self.as_ptr(), value.as_ptr()))
+            if jit_insn_move_blocks_to_end(self.as_ptr(), **from, **to) == 0 {
+                oom()
+            }
+        }
+    }
+    #[inline(always)]
+    /// Move the blocks from the first label up to the second label to the
+    /// start of the function
+    pub fn insn_move_blocks_to_start(&self, from: &Label<'a>, to: &Label<'a>) {
+        unsafe {
+            if jit_insn_move_blocks_to_start(self.as_ptr(), **from, **to) == 0 {
+                oom()
+            }
+        }
+    }
+    #[inline(always)]
+    /// Make an instruction that gets the call stack of the function, for
+    /// making stack traces
+    pub fn insn_get_call_stack(&self) -> Value<'a> {
+        unsafe {
+            from_ptr(jit_insn_get_call_stack(self.as_ptr()))
+        }
+    }
+    /// Make an instruction that calls the function in the vtable slot given
+    pub fn insn_call_indirect_vtable(&self, func: Value<'a>, signature: TypeRef,
+                                     args: &mut [Value<'a>], flags: flags::CallFlags) -> Value<'a> {
+        unsafe {
+            let mut native_args:Vec<_> = args.iter().map(|arg| arg.as_ptr()).collect();
+            from_ptr(jit_insn_call_indirect_vtable(
+                self.as_ptr(),
+                func.as_ptr(),
+                signature.as_ptr(),
+                native_args.as_mut_ptr(),
+                args.len() as c_uint,
+                flags.bits()
+            ))
+        }
+    }
+    /// Make an instruction that calls an intrinsic, which is a native
+    /// function LibJIT knows how to call and check the result of from the
+    /// descriptor given
+    ///
+    /// This is unsafe because the intrinsic must match the descriptor
+    pub unsafe fn insn_call_intrinsic(&self, name: Option<&str>, intrinsic: *mut c_void,
+                                      descriptor: &jit_intrinsic_descr_t,
+                                      arg1: Value<'a>, arg2: Option<Value<'a>>) -> Value<'a> {
+        let c_name = name.map(|name| CString::from_slice(name.as_bytes()));
+        from_ptr(jit_insn_call_intrinsic(
+            self.as_ptr(),
+            c_name.as_ref().map(|name| name.as_ptr()).unwrap_or(ptr::null()),
+            intrinsic,
+            descriptor,
+            arg1.as_ptr(),
+            arg2.as_ptr()
+        ))
+    }
+    #[inline(always)]
+    /// Make an instruction that says the value given comes into the
+    /// function in the register given
+    ///
+    /// This is unsafe because the register must be valid for the backend
+    pub unsafe fn insn_incoming_reg(&self, value: Value<'a>, reg: i32) {
+        if jit_insn_incoming_reg(self.as_ptr(), value.as_ptr(), reg as c_int) == 0 {
+            oom()
+        }
+    }
+    #[inline(always)]
+    /// Make an instruction that says the value given comes into the
+    /// function at the frame offset given
+    pub fn insn_incoming_frame_posn(&self, value: Value<'a>, frame_offset: isize) {
+        unsafe {
+            if jit_insn_incoming_frame_posn(self.as_ptr(), value.as_ptr(), frame_offset as jit_nint) == 0 {
+                oom()
+            }
+        }
+    }
+    #[inline(always)]
+    /// Make an instruction that puts the value given in the register given
+    /// for an outgoing call
+    ///
+    /// This is unsafe because the register must be valid for the backend
+    pub unsafe fn insn_outgoing_reg(&self, value: Value<'a>, reg: i32) {
+        if jit_insn_outgoing_reg(self.as_ptr(), value.as_ptr(), reg as c_int) == 0 {
+            oom()
+        }
+    }
+    #[inline(always)]
+    /// Make an instruction that puts the value given at the frame offset
+    /// given for an outgoing call
+    pub fn insn_outgoing_frame_posn(&self, value: Value<'a>, frame_offset: isize) {
+        unsafe {
+            if jit_insn_outgoing_frame_posn(self.as_ptr(), value.as_ptr(), frame_offset as jit_nint) == 0 {
+                oom()
+            }
+        }
+    }
+    #[inline(always)]
+    /// Make an instruction that says the value given is returned from a
+    /// call in the register given
+    ///
+    /// This is unsafe because the register must be valid for the backend
+    pub unsafe fn insn_return_reg(&self, value: Value<'a>, reg: i32) {
+        if jit_insn_return_reg(self.as_ptr(), value.as_ptr(), reg as c_int) == 0 {
+            oom()
+        }
+    }
+    #[inline(always)]
+    /// Make instructions that set up a call to a nested function at the
+    /// nesting level given, using the register given for the parent frame
+    ///
+    /// This is unsafe because the register must be valid for the backend
+    pub unsafe fn insn_setup_for_nested(&self, nested_level: i32, reg: i32) {
+        if jit_insn_setup_for_nested(self.as_ptr(), nested_level as c_int, reg as c_int) == 0 {
+            oom()
+        }
+    }
+    #[inline(always)]
+    /// Make an instruction that pushes the value the pointer given points
+    /// to, which is of the type given, onto the stack for an outgoing call
+    pub fn insn_push_ptr(&self, value: Value<'a>, ty: TypeRef) {
+        unsafe {
+            if jit_insn_push_ptr(self.as_ptr(), value.as_ptr(), ty.as_ptr()) == 0 {
+                oom()
+            }
+        }
+    }
+    #[inline(always)]
+    /// Make an instruction that puts the value given at the offset given in
+    /// the parameter area for an outgoing call
+    pub fn insn_set_param(&self, value: Value<'a>, offset: isize) {
+        unsafe {
+            if jit_insn_set_param(self.as_ptr(), value.as_ptr(), offset as jit_nint) == 0 {
+                oom()
+            }
+        }
+    }
+    #[inline(always)]
+    /// Make an instruction that puts the value the pointer given points to,
+    /// which is of the type given, at the offset given in the parameter area
+    /// for an outgoing call
+    pub fn insn_set_param_ptr(&self, value: Value<'a>, ty: TypeRef, offset: isize) {
+        unsafe {
+            if jit_insn_set_param_ptr(self.as_ptr(), value.as_ptr(), ty.as_ptr(), offset as jit_nint) == 0 {
+                oom()
+            }
+        }
+    }
+    #[inline(always)]
+    /// Make an instruction that pushes a pointer to the area the return
+    /// value of an outgoing call goes in
+    pub fn insn_push_return_area_ptr(&self) {
+        unsafe {
+            if jit_insn_push_return_area_ptr(self.as_ptr()) == 0 {
+                oom()
+            }
+        }
+    }
+    #[inline(always)]
+    /// Make an instruction that returns the value the pointer given points
+    /// to, which is of the type given
+    pub fn insn_return_ptr(&self, value: Value<'a>, ty: TypeRef) {
+        unsafe {
+            if jit_insn_return_ptr(self.as_ptr(), value.as_ptr(), ty.as_ptr()) == 0 {
+                oom()
+            }
         }
     }
     #[inline(always)]
@@ -837,18 +1313,87 @@ impl<'a> UncompiledFunction<'a> {
             jit_function_set_recompilable(self.as_ptr());
         }
     }
+    #[inline(always)]
+    /// Stop this function being a candidate for recompilation
+    pub fn clear_recompilable(&self) {
+        unsafe {
+            jit_function_clear_recompilable(self.as_ptr());
+        }
+    }
     /// Get the entry block of this function
     pub fn get_entry(&self) -> Option<Block<'a>> {
         unsafe {
             from_ptr(jit_function_get_entry(self.as_ptr()))
         }
     }
+    /// Iterate through the blocks of this function
+    pub fn blocks(&self) -> Blocks<'a> {
+        unsafe {
+            Blocks::new(self.as_ptr())
+        }
+    }
+    /// Make a Graphviz graph of the control flow of this function, where each
+    /// block lists its instructions and each edge is labelled `branch`,
+    /// `fallthrough` or `jump-table`
+    pub fn to_dot(&self) -> String {
+        let node = |label| unsafe {
+            Block::from_label(self.as_ptr(), label).map(|block| block.get_label()).unwrap_or(label)
+        };
+        let mut dot = String::new();
+        dot.push_str("digraph function {\n    node [shape=box, fontname=monospace];\n");
+        for block in self.blocks() {
+            let label = block.get_label();
+            let mut text = format!("L{}:\\l", label);
+            for insn in block.iter() {
+                text.push_str(&*format!("    {}\\l", insn));
+            }
+            dot.push_str(&*format!("    block{} [label=\"{}\"];\n", label, text));
+            let mut falls_through = !block.ends_in_dead();
+            if let Some(last) = block.iter_rev().next() {
+                if last.is_jump_table() {
+                    for target in last.get_jump_table().into_iter() {
+                        dot.push_str(&*format!("    block{} -> block{} [label=\"jump-table\"];\n", label, node(target)));
+                    }
+                } else if let Some(target) = last.get_label() {
+                    if last.is_branch() {
+                        dot.push_str(&*format!("    block{} -> block{} [label=\"branch\"];\n", label, node(target)));
+                        falls_through = falls_through && last.get_opcode() != Opcode::Br;
+                    }
+                }
+            }
+            if falls_through {
+                if let Some(next) = block.next() {
+                    dot.push_str(&*format!("    block{} -> block{} [label=\"fallthrough\"];\n", label, next.get_label()));
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
     /// Get the current block of this function
     pub fn get_current(&self) -> Option<Block<'a>> {
         unsafe {
             from_ptr(jit_function_get_current(self.as_ptr()))
         }
     }
+    /// Build the body of this function with the closure given the first time
+    /// it is called instead of now, and return it so it can be called.
+    ///
+    /// The closure runs while the context is locked for building, and the
+    /// function is compiled as soon as it returns. If compiling fails, the
+    /// closure runs again the next time the function is called.
+    pub fn set_on_demand<F>(self, cb: F) -> CompiledFunction<'a> where F:FnMut(&UncompiledFunction) + 'static {
+        if !self.owned {
+            panic!("The function must be owned")
+        }
+        unsafe {
+            let ptr = self.as_ptr();
+            mem::forget(self);
+            meta::with_function_map(ptr, |map| map.insert(Box::new(OnDemandFn(Box::new(cb)))));
+            jit_function_set_on_demand_compiler(ptr, Some(compile_on_demand));
+            from_ptr(ptr)
+        }
+    }
     #[inline(always)]
     /// Compile the function
     pub fn compile(self) -> CompiledFunction<'a> {
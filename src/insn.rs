@@ -1,10 +1,532 @@
 use raw::*;
+use libc::c_void;
 use std::marker::ContravariantLifetime;
-use std::{ffi, fmt, mem, str};
-use function::AnyFunction;
+use std::{ffi, fmt, mem, ptr, str};
+use std::sync::{Once, ONCE_INIT};
+use function::{AnyFunction, UncompiledFunction};
+use meta;
 use value::Value;
 use types::Type;
-use util::{from_ptr, NativeRef};
+use util::{self, from_ptr, NativeRef};
+
+/// The opcode flag for instructions that branch
+pub static OPCODE_IS_BRANCH: i32 = 0x1000;
+/// The opcode flag for instructions that call a function
+pub static OPCODE_IS_CALL: i32 = 0x2000;
+/// The opcode flag for instructions that call a native function
+pub static OPCODE_IS_CALL_EXTERNAL: i32 = 0x4000;
+/// The opcode flag for instructions that use registers
+pub static OPCODE_IS_REG: i32 = 0x8000;
+/// The opcode flag for instructions that take the address of a label
+pub static OPCODE_IS_ADDROF_LABEL: i32 = 0x10000;
+/// The opcode flag for jump tables
+pub static OPCODE_IS_JUMP_TABLE: i32 = 0x20000;
+fn opcode_info(code: i32) -> Option<&'static jit_opcode_info_t> {
+	unsafe {
+		if code >= 0 && (code as usize) < jit_opcodes.len() {
+			Some(&jit_opcodes[code as usize])
+		} else {
+			None
+		}
+	}
+}
+macro_rules! opcodes(
+	($($name:ident => $c_name:tt),+) => (
+		/// An opcode from LibJIT's opcode table, looked up by name so it
+		/// doesn't depend on how LibJIT numbers them. The numeric codes are
+		/// matched to names once per thread.
+		#[derive(Copy, Debug, Eq, PartialEq)]
+		pub enum Opcode {
+			$($name,)+
+			/// An opcode that isn't named here, with its numeric code
+			Other(i32)
+		}
+		impl Opcode {
+			/// Get the opcode with the name LibJIT gives it
+			pub fn from_name(name: &str) -> Option<Opcode> {
+				match name {
+					$($c_name => Some(Opcode::$name),)+
+					_ => None
+				}
+			}
+			/// Get the name LibJIT gives this opcode, if it is named here
+			pub fn get_name(self) -> Option<&'static str> {
+				match self {
+					$(Opcode::$name => Some($c_name),)+
+					Opcode::Other(_) => None
+				}
+			}
+		}
+	)
+);
+opcodes!{
+	Nop => "nop",
+	TruncSByte => "trunc_sbyte",
+	TruncUByte => "trunc_ubyte",
+	TruncShort => "trunc_short",
+	TruncUShort => "trunc_ushort",
+	TruncInt => "trunc_int",
+	TruncUInt => "trunc_uint",
+	CheckSByte => "check_sbyte",
+	CheckUByte => "check_ubyte",
+	CheckShort => "check_short",
+	CheckUShort => "check_ushort",
+	CheckInt => "check_int",
+	CheckUInt => "check_uint",
+	LowWord => "low_word",
+	ExpandInt => "expand_int",
+	ExpandUInt => "expand_uint",
+	CheckLowWord => "check_low_word",
+	CheckSignedLowWord => "check_signed_low_word",
+	CheckLong => "check_long",
+	CheckULong => "check_ulong",
+	Float32ToInt => "float32_to_int",
+	Float32ToUInt => "float32_to_uint",
+	Float32ToLong => "float32_to_long",
+	Float32ToULong => "float32_to_ulong",
+	CheckFloat32ToInt => "check_float32_to_int",
+	CheckFloat32ToUInt => "check_float32_to_uint",
+	CheckFloat32ToLong => "check_float32_to_long",
+	CheckFloat32ToULong => "check_float32_to_ulong",
+	IntToFloat32 => "int_to_float32",
+	UIntToFloat32 => "uint_to_float32",
+	LongToFloat32 => "long_to_float32",
+	ULongToFloat32 => "ulong_to_float32",
+	Float32ToFloat64 => "float32_to_float64",
+	Float64ToInt => "float64_to_int",
+	Float64ToUInt => "float64_to_uint",
+	Float64ToLong => "float64_to_long",
+	Float64ToULong => "float64_to_ulong",
+	CheckFloat64ToInt => "check_float64_to_int",
+	CheckFloat64ToUInt => "check_float64_to_uint",
+	CheckFloat64ToLong => "check_float64_to_long",
+	CheckFloat64ToULong => "check_float64_to_ulong",
+	IntToFloat64 => "int_to_float64",
+	UIntToFloat64 => "uint_to_float64",
+	LongToFloat64 => "long_to_float64",
+	ULongToFloat64 => "ulong_to_float64",
+	Float64ToFloat32 => "float64_to_float32",
+	NFloatToInt => "nfloat_to_int",
+	NFloatToUInt => "nfloat_to_uint",
+	NFloatToLong => "nfloat_to_long",
+	NFloatToULong => "nfloat_to_ulong",
+	CheckNFloatToInt => "check_nfloat_to_int",
+	CheckNFloatToUInt => "check_nfloat_to_uint",
+	CheckNFloatToLong => "check_nfloat_to_long",
+	CheckNFloatToULong => "check_nfloat_to_ulong",
+	IntToNFloat => "int_to_nfloat",
+	UIntToNFloat => "uint_to_nfloat",
+	LongToNFloat => "long_to_nfloat",
+	ULongToNFloat => "ulong_to_nfloat",
+	NFloatToFloat32 => "nfloat_to_float32",
+	NFloatToFloat64 => "nfloat_to_float64",
+	Float32ToNFloat => "float32_to_nfloat",
+	Float64ToNFloat => "float64_to_nfloat",
+	IAdd => "iadd",
+	IAddOvf => "iadd_ovf",
+	IAddOvfUn => "iadd_ovf_un",
+	ISub => "isub",
+	ISubOvf => "isub_ovf",
+	ISubOvfUn => "isub_ovf_un",
+	IMul => "imul",
+	IMulOvf => "imul_ovf",
+	IMulOvfUn => "imul_ovf_un",
+	IDiv => "idiv",
+	IDivUn => "idiv_un",
+	IRem => "irem",
+	IRemUn => "irem_un",
+	INeg => "ineg",
+	LAdd => "ladd",
+	LAddOvf => "ladd_ovf",
+	LAddOvfUn => "ladd_ovf_un",
+	LSub => "lsub",
+	LSubOvf => "lsub_ovf",
+	LSubOvfUn => "lsub_ovf_un",
+	LMul => "lmul",
+	LMulOvf => "lmul_ovf",
+	LMulOvfUn => "lmul_ovf_un",
+	LDiv => "ldiv",
+	LDivUn => "ldiv_un",
+	LRem => "lrem",
+	LRemUn => "lrem_un",
+	LNeg => "lneg",
+	FAdd => "fadd",
+	FSub => "fsub",
+	FMul => "fmul",
+	FDiv => "fdiv",
+	FRem => "frem",
+	FRemIeee => "frem_ieee",
+	FNeg => "fneg",
+	DAdd => "dadd",
+	DSub => "dsub",
+	DMul => "dmul",
+	DDiv => "ddiv",
+	DRem => "drem",
+	DRemIeee => "drem_ieee",
+	DNeg => "dneg",
+	NFAdd => "nfadd",
+	NFSub => "nfsub",
+	NFMul => "nfmul",
+	NFDiv => "nfdiv",
+	NFRem => "nfrem",
+	NFRemIeee => "nfrem_ieee",
+	NFNeg => "nfneg",
+	IAnd => "iand",
+	IOr => "ior",
+	IXor => "ixor",
+	INot => "inot",
+	IShl => "ishl",
+	IShr => "ishr",
+	IShrUn => "ishr_un",
+	LAnd => "land",
+	LOr => "lor",
+	LXor => "lxor",
+	LNot => "lnot",
+	LShl => "lshl",
+	LShr => "lshr",
+	LShrUn => "lshr_un",
+	Br => "br",
+	BrIFalse => "br_ifalse",
+	BrITrue => "br_itrue",
+	BrIEq => "br_ieq",
+	BrINe => "br_ine",
+	BrILt => "br_ilt",
+	BrILtUn => "br_ilt_un",
+	BrILe => "br_ile",
+	BrILeUn => "br_ile_un",
+	BrIGt => "br_igt",
+	BrIGtUn => "br_igt_un",
+	BrIGe => "br_ige",
+	BrIGeUn => "br_ige_un",
+	BrLFalse => "br_lfalse",
+	BrLTrue => "br_ltrue",
+	BrLEq => "br_leq",
+	BrLNe => "br_lne",
+	BrLLt => "br_llt",
+	BrLLtUn => "br_llt_un",
+	BrLLe => "br_lle",
+	BrLLeUn => "br_lle_un",
+	BrLGt => "br_lgt",
+	BrLGtUn => "br_lgt_un",
+	BrLGe => "br_lge",
+	BrLGeUn => "br_lge_un",
+	BrFEq => "br_feq",
+	BrFNe => "br_fne",
+	BrFLt => "br_flt",
+	BrFLe => "br_fle",
+	BrFGt => "br_fgt",
+	BrFGe => "br_fge",
+	BrFLtInv => "br_flt_inv",
+	BrFLeInv => "br_fle_inv",
+	BrFGtInv => "br_fgt_inv",
+	BrFGeInv => "br_fge_inv",
+	BrDEq => "br_deq",
+	BrDNe => "br_dne",
+	BrDLt => "br_dlt",
+	BrDLe => "br_dle",
+	BrDGt => "br_dgt",
+	BrDGe => "br_dge",
+	BrDLtInv => "br_dlt_inv",
+	BrDLeInv => "br_dle_inv",
+	BrDGtInv => "br_dgt_inv",
+	BrDGeInv => "br_dge_inv",
+	BrNFEq => "br_nfeq",
+	BrNFNe => "br_nfne",
+	BrNFLt => "br_nflt",
+	BrNFLe => "br_nfle",
+	BrNFGt => "br_nfgt",
+	BrNFGe => "br_nfge",
+	BrNFLtInv => "br_nflt_inv",
+	BrNFLeInv => "br_nfle_inv",
+	BrNFGtInv => "br_nfgt_inv",
+	BrNFGeInv => "br_nfge_inv",
+	ICmp => "icmp",
+	ICmpUn => "icmp_un",
+	LCmp => "lcmp",
+	LCmpUn => "lcmp_un",
+	FCmpl => "fcmpl",
+	FCmpg => "fcmpg",
+	DCmpl => "dcmpl",
+	DCmpg => "dcmpg",
+	NFCmpl => "nfcmpl",
+	NFCmpg => "nfcmpg",
+	IEq => "ieq",
+	INe => "ine",
+	ILt => "ilt",
+	ILtUn => "ilt_un",
+	ILe => "ile",
+	ILeUn => "ile_un",
+	IGt => "igt",
+	IGtUn => "igt_un",
+	IGe => "ige",
+	IGeUn => "ige_un",
+	LEq => "leq",
+	LNe => "lne",
+	LLt => "llt",
+	LLtUn => "llt_un",
+	LLe => "lle",
+	LLeUn => "lle_un",
+	LGt => "lgt",
+	LGtUn => "lgt_un",
+	LGe => "lge",
+	LGeUn => "lge_un",
+	FEq => "feq",
+	FNe => "fne",
+	FLt => "flt",
+	FLe => "fle",
+	FGt => "fgt",
+	FGe => "fge",
+	FLtInv => "flt_inv",
+	FLeInv => "fle_inv",
+	FGtInv => "fgt_inv",
+	FGeInv => "fge_inv",
+	DEq => "deq",
+	DNe => "dne",
+	DLt => "dlt",
+	DLe => "dle",
+	DGt => "dgt",
+	DGe => "dge",
+	DLtInv => "dlt_inv",
+	DLeInv => "dle_inv",
+	DGtInv => "dgt_inv",
+	DGeInv => "dge_inv",
+	NFEq => "nfeq",
+	NFNe => "nfne",
+	NFLt => "nflt",
+	NFLe => "nfle",
+	NFGt => "nfgt",
+	NFGe => "nfge",
+	NFLtInv => "nflt_inv",
+	NFLeInv => "nfle_inv",
+	NFGtInv => "nfgt_inv",
+	NFGeInv => "nfge_inv",
+	IsFNan => "is_fnan",
+	IsFInf => "is_finf",
+	IsFFinite => "is_ffinite",
+	IsDNan => "is_dnan",
+	IsDInf => "is_dinf",
+	IsDFinite => "is_dfinite",
+	IsNFNan => "is_nfnan",
+	IsNFInf => "is_nfinf",
+	IsNFFinite => "is_nffinite",
+	FAcos => "facos",
+	FAsin => "fasin",
+	FAtan => "fatan",
+	FAtan2 => "fatan2",
+	FCeil => "fceil",
+	FCos => "fcos",
+	FCosh => "fcosh",
+	FExp => "fexp",
+	FFloor => "ffloor",
+	FLog => "flog",
+	FLog10 => "flog10",
+	FPow => "fpow",
+	FRint => "frint",
+	FRound => "fround",
+	FSin => "fsin",
+	FSinh => "fsinh",
+	FSqrt => "fsqrt",
+	FTan => "ftan",
+	FTanh => "ftanh",
+	FTrunc => "ftrunc",
+	DAcos => "dacos",
+	DAsin => "dasin",
+	DAtan => "datan",
+	DAtan2 => "datan2",
+	DCeil => "dceil",
+	DCos => "dcos",
+	DCosh => "dcosh",
+	DExp => "dexp",
+	DFloor => "dfloor",
+	DLog => "dlog",
+	DLog10 => "dlog10",
+	DPow => "dpow",
+	DRint => "drint",
+	DRound => "dround",
+	DSin => "dsin",
+	DSinh => "dsinh",
+	DSqrt => "dsqrt",
+	DTan => "dtan",
+	DTanh => "dtanh",
+	DTrunc => "dtrunc",
+	NFAcos => "nfacos",
+	NFAsin => "nfasin",
+	NFAtan => "nfatan",
+	NFAtan2 => "nfatan2",
+	NFCeil => "nfceil",
+	NFCos => "nfcos",
+	NFCosh => "nfcosh",
+	NFExp => "nfexp",
+	NFFloor => "nffloor",
+	NFLog => "nflog",
+	NFLog10 => "nflog10",
+	NFPow => "nfpow",
+	NFRint => "nfrint",
+	NFRound => "nfround",
+	NFSin => "nfsin",
+	NFSinh => "nfsinh",
+	NFSqrt => "nfsqrt",
+	NFTan => "nftan",
+	NFTanh => "nftanh",
+	NFTrunc => "nftrunc",
+	IAbs => "iabs",
+	LAbs => "labs",
+	FAbs => "fabs",
+	DAbs => "dabs",
+	NFAbs => "nfabs",
+	IMin => "imin",
+	IMinUn => "imin_un",
+	LMin => "lmin",
+	LMinUn => "lmin_un",
+	FMin => "fmin",
+	DMin => "dmin",
+	NFMin => "nfmin",
+	IMax => "imax",
+	IMaxUn => "imax_un",
+	LMax => "lmax",
+	LMaxUn => "lmax_un",
+	FMax => "fmax",
+	DMax => "dmax",
+	NFMax => "nfmax",
+	ISign => "isign",
+	LSign => "lsign",
+	FSign => "fsign",
+	DSign => "dsign",
+	NFSign => "nfsign",
+	CheckNull => "check_null",
+	Call => "call",
+	CallTail => "call_tail",
+	CallIndirect => "call_indirect",
+	CallIndirectTail => "call_indirect_tail",
+	CallVtablePtr => "call_vtable_ptr",
+	CallVtablePtrTail => "call_vtable_ptr_tail",
+	CallExternal => "call_external",
+	CallExternalTail => "call_external_tail",
+	Return => "return",
+	ReturnInt => "return_int",
+	ReturnLong => "return_long",
+	ReturnFloat32 => "return_float32",
+	ReturnFloat64 => "return_float64",
+	ReturnNFloat => "return_nfloat",
+	ReturnSmallStruct => "return_small_struct",
+	SetupForNested => "setup_for_nested",
+	SetupForSibling => "setup_for_sibling",
+	Import => "import",
+	Throw => "throw",
+	Rethrow => "rethrow",
+	LoadPc => "load_pc",
+	LoadExceptionPc => "load_exception_pc",
+	EnterFinally => "enter_finally",
+	LeaveFinally => "leave_finally",
+	CallFinally => "call_finally",
+	EnterFilter => "enter_filter",
+	LeaveFilter => "leave_filter",
+	CallFilter => "call_filter",
+	CallFilterReturn => "call_filter_return",
+	AddressOfLabel => "address_of_label",
+	CopyLoadSByte => "copy_load_sbyte",
+	CopyLoadUByte => "copy_load_ubyte",
+	CopyLoadShort => "copy_load_short",
+	CopyLoadUShort => "copy_load_ushort",
+	CopyInt => "copy_int",
+	CopyLong => "copy_long",
+	CopyFloat32 => "copy_float32",
+	CopyFloat64 => "copy_float64",
+	CopyNFloat => "copy_nfloat",
+	CopyStruct => "copy_struct",
+	CopyStoreByte => "copy_store_byte",
+	CopyStoreShort => "copy_store_short",
+	AddressOf => "address_of",
+	IncomingReg => "incoming_reg",
+	IncomingFramePosn => "incoming_frame_posn",
+	OutgoingReg => "outgoing_reg",
+	OutgoingFramePosn => "outgoing_frame_posn",
+	ReturnReg => "return_reg",
+	PushInt => "push_int",
+	PushLong => "push_long",
+	PushFloat32 => "push_float32",
+	PushFloat64 => "push_float64",
+	PushNFloat => "push_nfloat",
+	PushStruct => "push_struct",
+	PopStack => "pop_stack",
+	FlushSmallStruct => "flush_small_struct",
+	SetParamInt => "set_param_int",
+	SetParamLong => "set_param_long",
+	SetParamFloat32 => "set_param_float32",
+	SetParamFloat64 => "set_param_float64",
+	SetParamNFloat => "set_param_nfloat",
+	SetParamStruct => "set_param_struct",
+	PushReturnAreaPtr => "push_return_area_ptr",
+	LoadRelativeSByte => "load_relative_sbyte",
+	LoadRelativeUByte => "load_relative_ubyte",
+	LoadRelativeShort => "load_relative_short",
+	LoadRelativeUShort => "load_relative_ushort",
+	LoadRelativeInt => "load_relative_int",
+	LoadRelativeLong => "load_relative_long",
+	LoadRelativeFloat32 => "load_relative_float32",
+	LoadRelativeFloat64 => "load_relative_float64",
+	LoadRelativeNFloat => "load_relative_nfloat",
+	LoadRelativeStruct => "load_relative_struct",
+	StoreRelativeByte => "store_relative_byte",
+	StoreRelativeShort => "store_relative_short",
+	StoreRelativeInt => "store_relative_int",
+	StoreRelativeLong => "store_relative_long",
+	StoreRelativeFloat32 => "store_relative_float32",
+	StoreRelativeFloat64 => "store_relative_float64",
+	StoreRelativeNFloat => "store_relative_nfloat",
+	StoreRelativeStruct => "store_relative_struct",
+	AddRelative => "add_relative",
+	LoadElementSByte => "load_element_sbyte",
+	LoadElementUByte => "load_element_ubyte",
+	LoadElementShort => "load_element_short",
+	LoadElementUShort => "load_element_ushort",
+	LoadElementInt => "load_element_int",
+	LoadElementLong => "load_element_long",
+	LoadElementFloat32 => "load_element_float32",
+	LoadElementFloat64 => "load_element_float64",
+	LoadElementNFloat => "load_element_nfloat",
+	StoreElementByte => "store_element_byte",
+	StoreElementShort => "store_element_short",
+	StoreElementInt => "store_element_int",
+	StoreElementLong => "store_element_long",
+	StoreElementFloat32 => "store_element_float32",
+	StoreElementFloat64 => "store_element_float64",
+	StoreElementNFloat => "store_element_nfloat",
+	Memcpy => "memcpy",
+	Memmove => "memmove",
+	Memset => "memset",
+	Alloca => "alloca",
+	MarkOffset => "mark_offset",
+	MarkBreakpoint => "mark_breakpoint",
+	JumpTable => "jump_table"
+}
+static CODES_INIT: Once = ONCE_INIT;
+/// The opcode for each numeric code, which is made the first time any thread
+/// needs it and lives for the rest of the process
+static mut CODES: *const Vec<Opcode> = 0 as *const Vec<Opcode>;
+fn codes() -> &'static [Opcode] {
+	unsafe {
+		CODES_INIT.call_once(|| {
+			let codes = range(0, jit_opcodes.len()).map(|code| {
+				opcode_info(code as i32).and_then(|info| {
+					let name: &*const i8 = mem::transmute(&info.name);
+					str::from_utf8(ffi::c_str_to_bytes(name)).ok().and_then(Opcode::from_name)
+				}).unwrap_or(Opcode::Other(code as i32))
+			}).collect::<Vec<_>>();
+			CODES = mem::transmute(Box::new(codes));
+		});
+		&**CODES
+	}
+}
+impl Opcode {
+	/// Get the opcode with the numeric code given
+	pub fn from_code(code: i32) -> Opcode {
+		if code < 0 {
+			return Opcode::Other(code)
+		}
+		codes().get(code as usize).map(|&opcode| opcode).unwrap_or(Opcode::Other(code))
+	}
+}
 
 /// Represents a single LibJIT instruction
 native_ref!(Instruction ContravariantLifetime {
@@ -14,49 +536,108 @@ impl<'a> Copy for Instruction<'a> {}
 
 impl<'a> Instruction<'a> {
 	/// Get the opcode of the instruction
-	fn get_opcode(self) -> i32 {
+	pub fn get_opcode(self) -> Opcode {
+		Opcode::from_code(self.get_code())
+	}
+	/// Get the numeric opcode of the instruction
+	pub fn get_code(self) -> i32 {
 		unsafe {
 			jit_insn_get_opcode(self._insn)
 		}
 	}
+	/// Get the flags LibJIT gives the opcode of the instruction
+	pub fn get_flags(self) -> i32 {
+		opcode_info(self.get_code()).map(|info| info.flags).unwrap_or(0)
+	}
+	/// Check if the instruction is a branch
+	pub fn is_branch(self) -> bool {
+		self.get_flags() & OPCODE_IS_BRANCH != 0
+	}
+	/// Check if the instruction is a call
+	pub fn is_call(self) -> bool {
+		self.get_flags() & (OPCODE_IS_CALL | OPCODE_IS_CALL_EXTERNAL) != 0
+	}
+	/// Check if the instruction is a jump table
+	pub fn is_jump_table(self) -> bool {
+		self.get_flags() & OPCODE_IS_JUMP_TABLE != 0
+	}
+	/// Get the label this instruction branches to, if it is a branch
+	pub fn get_label(self) -> Option<jit_label_t> {
+		if self.is_branch() || self.get_flags() & OPCODE_IS_ADDROF_LABEL != 0 {
+			unsafe {
+				Some(jit_insn_get_label(self._insn))
+			}
+		} else {
+			None
+		}
+	}
+	/// Get the labels this instruction can jump to, if it is a jump table
+	pub fn get_jump_table(self) -> Vec<jit_label_t> {
+		if !self.is_jump_table() {
+			return Vec::new()
+		}
+		unsafe {
+			let labels = jit_value_get_nint_constant(jit_insn_get_value1(self._insn)) as *const jit_label_t;
+			let count = jit_value_get_nint_constant(jit_insn_get_value2(self._insn)) as usize;
+			range(0, count).map(|i| *labels.offset(i as isize)).collect()
+		}
+	}
+	/// Get the native function this instruction calls, if it calls one
+	pub fn get_native(self) -> Option<*mut c_void> {
+		unsafe {
+			let native = jit_insn_get_native(self._insn);
+			if native.is_null() {
+				None
+			} else {
+				Some(native)
+			}
+		}
+	}
 	/// Get the destination value
-	fn get_dest(self) -> Option<Value<'a>> {
+	pub fn get_dest(self) -> Option<Value<'a>> {
 		unsafe {
 			from_ptr(jit_insn_get_dest(self._insn))
 		}
 	}
 	/// Get if the destination value is a value
-	fn dest_is_value(self) -> bool {
+	pub fn dest_is_value(self) -> bool {
 		unsafe {
 			jit_insn_dest_is_value(self._insn) != 0
 		}
 	}
 	/// Get the left value
-	fn get_value1(self) -> Option<Value<'a>> {
+	pub fn get_value1(self) -> Option<Value<'a>> {
 		unsafe {
 			from_ptr(jit_insn_get_value1(self._insn))
 		}
 	}
 	/// Get the right value
-	fn get_value2(self) -> Option<Value<'a>> {
+	pub fn get_value2(self) -> Option<Value<'a>> {
 		unsafe {
 			from_ptr(jit_insn_get_value2(self._insn))
 		}
 	}
 	/// Get the function containing this value
-	fn get_function(self) -> Option<AnyFunction<'a>> {
+	pub fn get_function(self) -> Option<AnyFunction<'a>> {
 		unsafe {
 			from_ptr(jit_insn_get_function(self._insn))
 		}
 	}
 	/// Get the signature of this value
-	fn get_signature(self) -> Option<Type> {
+	pub fn get_signature(self) -> Option<Type> {
 		unsafe {
 			from_ptr(jit_insn_get_signature(self._insn))
 		}
 	}
+	/// Dump the instruction the way LibJIT would. The function given must be
+	/// the one containing this instruction.
+	pub fn dump(self, func: &UncompiledFunction<'a>) -> Result<String, fmt::Error> {
+		util::dump(|fd| unsafe {
+			jit_dump_insn(mem::transmute(fd), func.as_ptr(), self._insn);
+		})
+	}
 	/// Get the name of the instruction
-	fn get_name(self) -> &'a str {
+	pub fn get_name(self) -> &'a str {
 		unsafe {
 			let name = jit_insn_get_name(self._insn);
 			let name: &*const i8 = mem::transmute(&name);
@@ -65,11 +646,36 @@ impl<'a> Instruction<'a> {
 	}
 }
 impl<'a> fmt::Display for Instruction<'a> {
+	/// Write the instruction as `dest = name value1, value2`, followed by the
+	/// labels it branches to and the native function it calls
 	fn fmt(&self, fmt:&mut fmt::Formatter) -> fmt::Result {
-		write!(fmt, "{}", self.get_name())
+		let mut operands = Vec::new();
+		match self.get_dest() {
+			Some(dest) if self.dest_is_value() => operands.push(dest),
+			Some(dest) => try!(write!(fmt, "{} = ", dest)),
+			None => ()
+		}
+		try!(fmt.write_str(self.get_name()));
+		if self.is_jump_table() {
+			let labels = self.get_jump_table().into_iter().map(|label| format!("L{}", label)).collect::<Vec<_>>();
+			return write!(fmt, " [{}]", labels.connect(", "))
+		}
+		operands.extend(self.get_value1().into_iter());
+		operands.extend(self.get_value2().into_iter());
+		for (index, operand) in operands.iter().enumerate() {
+			try!(write!(fmt, "{}{}", if index == 0 { " " } else { ", " }, operand));
+		}
+		if let Some(label) = self.get_label() {
+			try!(write!(fmt, " L{}", label));
+		}
+		match self.get_native() {
+			Some(native) => write!(fmt, " {:p}", native),
+			None => Ok(())
+		}
 	}
 }
 
+/// Iterates through the instructions of a block from first to last
 pub struct InstructionIter<'a> {
 	_iter: jit_insn_iter_t,
 	marker: ContravariantLifetime<'a>
@@ -82,6 +688,19 @@ impl<'a> Iterator for InstructionIter<'a> {
 		}
 	}
 }
+/// Iterates through the instructions of a block from last to first
+pub struct RevInstructionIter<'a> {
+	_iter: jit_insn_iter_t,
+	marker: ContravariantLifetime<'a>
+}
+impl<'a> Iterator for RevInstructionIter<'a> {
+	type Item = Instruction<'a>;
+	fn next(&mut self) -> Option<Instruction<'a>> {
+		unsafe {
+			from_ptr(jit_insn_iter_previous(&mut self._iter))
+		}
+	}
+}
 
 /// Represents a single LibJIT block
 native_ref!(Block ContravariantLifetime {
@@ -95,6 +714,28 @@ impl<'a> Block<'a> {
 			from_ptr(jit_block_get_function(self._block))
 		}
 	}
+	/// Get the label at the start of the block
+	pub fn get_label(self) -> jit_label_t {
+		unsafe {
+			jit_block_get_label(self._block)
+		}
+	}
+	/// Get the block that starts at the label given in the function given
+	pub unsafe fn from_label(func: jit_function_t, label: jit_label_t) -> Option<Block<'a>> {
+		from_ptr(jit_block_from_label(func, label))
+	}
+	/// Get the block after this one in its function
+	pub fn next(self) -> Option<Block<'a>> {
+		unsafe {
+			from_ptr(jit_block_next(jit_block_get_function(self._block), self._block))
+		}
+	}
+	/// Get the block before this one in its function
+	pub fn previous(self) -> Option<Block<'a>> {
+		unsafe {
+			from_ptr(jit_block_previous(jit_block_get_function(self._block), self._block))
+		}
+	}
 	/// Check if the block is reachable
 	pub fn is_reachable(self) -> bool {
 		unsafe {
@@ -107,6 +748,29 @@ impl<'a> Block<'a> {
 			jit_block_ends_in_dead(self._block) != 0
 		}
 	}
+	/// Get the metadata of the type given that is attached to this block
+	pub fn get_meta<T>(&self) -> Option<&T> where T:'static {
+		unsafe {
+			let data = meta::with_block_map(self._block, |map| map.get::<T>().map(|data| data as *const T));
+			data.map(|data| &*data)
+		}
+	}
+	/// Attach some metadata to this block, replacing any of the same type.
+	/// The old metadata is kept until the block is freed, since another
+	/// handle to this block could still be borrowing it.
+	pub fn set_meta<T>(&self, data: Box<T>) where T:'static {
+		unsafe {
+			meta::with_block_map(self._block, |map| map.replace(data))
+		}
+	}
+	/// Detach the metadata of the type given from this block, returning true
+	/// if there was any. It is kept until the block is freed, since another
+	/// handle to this block could still be borrowing it.
+	pub fn remove_meta<T>(&self) -> bool where T:'static {
+		unsafe {
+			meta::with_block_map(self._block, |map| map.retire::<T>())
+		}
+	}
 	/// Iterate through the instructions
 	pub fn iter(self) -> InstructionIter<'a> {
 		unsafe {
@@ -120,4 +784,40 @@ impl<'a> Block<'a> {
 			}
 		}
 	}
-}
\ No newline at end of file
+	/// Iterate through the instructions from last to first
+	pub fn iter_rev(self) -> RevInstructionIter<'a> {
+		unsafe {
+			let mut iter = mem::zeroed();
+			jit_insn_iter_init_last(&mut iter, self._block);
+			RevInstructionIter {
+				_iter: iter,
+				marker: ContravariantLifetime::<'a>
+			}
+		}
+	}
+}
+/// Iterates through the blocks of a function
+pub struct Blocks<'a> {
+	function: jit_function_t,
+	last: jit_block_t,
+	marker: ContravariantLifetime<'a>
+}
+impl<'a> Blocks<'a> {
+	/// Iterate through the blocks of the function given
+	pub unsafe fn new(function: jit_function_t) -> Blocks<'a> {
+		Blocks {
+			function: function,
+			last: ptr::null_mut(),
+			marker: ContravariantLifetime::<'a>
+		}
+	}
+}
+impl<'a> Iterator for Blocks<'a> {
+	type Item = Block<'a>;
+	fn next(&mut self) -> Option<Block<'a>> {
+		unsafe {
+			self.last = jit_block_next(self.function, self.last);
+			from_ptr(self.last)
+		}
+	}
+}
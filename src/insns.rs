@@ -0,0 +1,245 @@
+use raw::*;
+use alloc::oom;
+use function::{CompiledFunction, UncompiledFunction};
+use insn::{Block, Instruction, InstructionIter, RevInstructionIter};
+use util::{from_ptr, NativeRef};
+use value::Value;
+// Every instruction function LibJIT has, so wrapping each one can be checked.
+// Ones whose arguments don't fit a section are wrapped by hand and listed
+// under `manual` along with their wrapper, which must exist to compile.
+insns!{
+    unary {
+        /// Make an instruction that performs a bitwise not on the value
+        fn insn_not => jit_insn_not;
+        /// Make an instruction that performs a bitwise negate on the value
+        fn insn_neg => jit_insn_neg;
+        /// Make an instruction that loads a value from a src value
+        fn insn_load => jit_insn_load;
+        /// Make an instruction that gets the inverse cosine of the number given
+        fn insn_acos => jit_insn_acos;
+        /// Make an instruction that gets the inverse sine of the number given
+        fn insn_asin => jit_insn_asin;
+        /// Make an instruction that gets the inverse tangent of the number given
+        fn insn_atan => jit_insn_atan;
+        /// Make an instruction that finds the nearest integer above a number
+        fn insn_ceil => jit_insn_ceil;
+        /// Make an instruction that gets the cosine of the number given
+        fn insn_cos => jit_insn_cos;
+        /// Make an instruction that gets the hyperbolic cosine of the number given
+        fn insn_cosh => jit_insn_cosh;
+        /// Make an instruction that gets e raised to the power of the number
+        fn insn_exp => jit_insn_exp;
+        /// Make an instruction that finds the nearest integer below a number
+        fn insn_floor => jit_insn_floor;
+        /// Make an instruction that gets the natural logarithm of the number
+        fn insn_log => jit_insn_log;
+        /// Make an instruction that gets the base 10 logarithm of the number
+        fn insn_log10 => jit_insn_log10;
+        /// Make an instruction that gets the result of rounding the value to the
+        /// nearest integer
+        fn insn_rint => jit_insn_rint;
+        /// Make an instruction that gets the result of rounding the value to the
+        /// nearest integer
+        fn insn_round => jit_insn_round;
+        /// Make an instruction that gets the sine of the number
+        fn insn_sin => jit_insn_sin;
+        /// Make an instruction that gets the hyperbolic sine of the number
+        fn insn_sinh => jit_insn_sinh;
+        /// Make an instruction that gets the square root of a number
+        fn insn_sqrt => jit_insn_sqrt;
+        /// Make an instruction that gets the tangent of a number
+        fn insn_tan => jit_insn_tan;
+        /// Make an instruction that gets the hyperbolic tangent of a number
+        fn insn_tanh => jit_insn_tanh;
+        /// Make an instruction that truncates the value
+        fn insn_trunc => jit_insn_trunc;
+        /// Make an instruction that checks if the number is NaN
+        fn insn_is_nan => jit_insn_is_nan;
+        /// Make an instruction that checks if the number is finite
+        fn insn_is_finite => jit_insn_is_finite;
+        /// Make an instruction that checks if the number is  infinite
+        fn insn_is_inf => jit_insn_is_inf;
+        /// Make an instruction that gets the absolute value of a number
+        fn insn_abs => jit_insn_abs;
+        /// Make an instruction that gets the sign of a number
+        fn insn_sign => jit_insn_sign;
+        /// Make an instruction that loads a small value, widening it to a full
+        /// integer
+        fn insn_load_small => jit_insn_load_small;
+        /// Make an instruction that converts the value to a boolean, which is 1
+        /// if it is not zero and 0 otherwise
+        fn insn_to_bool => jit_insn_to_bool;
+        /// Make an instruction that converts the value to an inverted boolean,
+        /// which is 1 if it is zero and 0 otherwise
+        fn insn_to_not_bool => jit_insn_to_not_bool;
+        /// Make an instruction that imports a value from the parent of a nested
+        /// function, getting a pointer to it
+        fn insn_import => jit_insn_import;
+    }
+    binary {
+        /// Make an instruction that multiplies the values
+        fn insn_mul => jit_insn_mul;
+        /// Make an instruction that multiplies the values and throws upon overflow
+        fn insn_mul_ovf => jit_insn_mul_ovf;
+        /// Make an instruction that adds the values
+        fn insn_add => jit_insn_add;
+        /// Make an instruction that adds the values and throws upon overflow
+        fn insn_add_ovf => jit_insn_add_ovf;
+        /// Make an instruction that subtracts the second value from the first
+        fn insn_sub => jit_insn_sub;
+        /// Make an instruction that subtracts the second value from the first and throws upon overflow
+        fn insn_sub_ovf => jit_insn_sub_ovf;
+        /// Make an instruction that divides the first number by the second
+        fn insn_div => jit_insn_div;
+        /// Make an instruction that finds the remainder when the first number is
+        /// divided by the second
+        fn insn_rem => jit_insn_rem;
+        /// Make an instruction that checks if the first value is lower than or
+        /// equal to the second
+        fn insn_leq => jit_insn_le;
+        /// Make an instruction that checks if the first value is greater than or
+        /// equal to the second
+        fn insn_geq => jit_insn_ge;
+        /// Make an instruction that checks if the first value is lower than the second
+        fn insn_lt => jit_insn_lt;
+        /// Make an instruction that checks if the first value is greater than the second
+        fn insn_gt => jit_insn_gt;
+        /// Make an instruction that checks if the values are equal
+        fn insn_eq => jit_insn_eq;
+        /// Make an instruction that checks if the values are not equal
+        fn insn_neq => jit_insn_ne;
+        /// Make an instruction that performs a bitwise and on the two values
+        fn insn_and => jit_insn_and;
+        /// Make an instruction that performs a bitwise or on the two values
+        fn insn_or => jit_insn_or;
+        /// Make an instruction that performs a bitwise xor on the two values
+        fn insn_xor => jit_insn_xor;
+        /// Make an instruction that performs a left bitwise shift on the first
+        /// value by the second value
+        fn insn_shl => jit_insn_shl;
+        /// Make an instruction that performs a right bitwise shift on the first
+        /// value by the second value
+        fn insn_shr => jit_insn_shr;
+        /// Make an instruction that performs a right bitwise shift on the first
+        /// value by the second value
+        fn insn_ushr => jit_insn_ushr;
+        /// Make an instruction that gets the inverse tangent of the numbers given
+        fn insn_atan2 => jit_insn_atan2;
+        /// Make an instruction that gets the result of raising the first value to
+        /// the power of the second value
+        fn insn_pow => jit_insn_pow;
+        /// Make an instruction that gets the smallest of two numbers
+        fn insn_min => jit_insn_min;
+        /// Make an instruction that gets the biggest of two numbers
+        fn insn_max => jit_insn_max;
+        /// Make an instruction that finds the IEEE remainder when the first
+        /// number is divided by the second
+        fn insn_rem_ieee => jit_insn_rem_ieee;
+        /// Make an instruction that performs a signed right bitwise shift on the
+        /// first value by the second value
+        fn insn_sshr => jit_insn_sshr;
+        /// Make an instruction that compares the values, giving -1 if the first
+        /// is lower, 0 if they are equal and 1 if it is greater. If either is
+        /// NaN, this gives -1.
+        fn insn_cmpl => jit_insn_cmpl;
+        /// Make an instruction that compares the values, giving -1 if the first
+        /// is lower, 0 if they are equal and 1 if it is greater. If either is
+        /// NaN, this gives 1.
+        fn insn_cmpg => jit_insn_cmpg;
+    }
+    statement {
+        /// Make an instruction that throws `NullReference` if the value is null
+        fn insn_check_null => jit_insn_check_null;
+        /// Make an instruction that flushes a struct value held in registers back
+        /// to memory
+        fn insn_flush_struct => jit_insn_flush_struct;
+        /// Make an instruction that pushes the value onto the stack for an
+        /// outgoing call
+        fn insn_push => jit_insn_push;
+    }
+    stack {
+        /// Make an instruction that pops the number of items given from the
+        /// stack after an outgoing call
+        fn insn_pop_stack => jit_insn_pop_stack;
+        /// Make an instruction that pops the number of items given from the
+        /// stack after an outgoing call, deferring it so it can be merged with
+        /// later pops
+        fn insn_defer_pop_stack => jit_insn_defer_pop_stack;
+        /// Make an instruction that flushes deferred stack pops if there are at
+        /// least the number of items given
+        fn insn_flush_defer_pop => jit_insn_flush_defer_pop;
+    }
+    manual {
+        jit_insn_get_opcode => Instruction::get_code,
+        jit_insn_get_dest => Instruction::get_dest,
+        jit_insn_get_value1 => Instruction::get_value1,
+        jit_insn_get_value2 => Instruction::get_value2,
+        jit_insn_get_label => Instruction::get_label,
+        jit_insn_get_function => Instruction::get_function,
+        jit_insn_get_native => Instruction::get_native,
+        jit_insn_get_name => Instruction::get_name,
+        jit_insn_get_signature => Instruction::get_signature,
+        jit_insn_dest_is_value => Instruction::dest_is_value,
+        jit_insn_label => UncompiledFunction::insn_label,
+        jit_insn_new_block => UncompiledFunction::insn_new_block,
+        jit_insn_dup => <Value as Clone>::clone,
+        jit_insn_store => UncompiledFunction::insn_store,
+        jit_insn_load_relative => UncompiledFunction::insn_load_relative,
+        jit_insn_store_relative => UncompiledFunction::insn_store_relative,
+        jit_insn_add_relative => UncompiledFunction::insn_add_relative,
+        jit_insn_load_elem => UncompiledFunction::insn_load_elem,
+        jit_insn_load_elem_address => UncompiledFunction::insn_load_elem_address,
+        jit_insn_store_elem => UncompiledFunction::insn_store_elem,
+        jit_insn_branch => UncompiledFunction::insn_branch,
+        jit_insn_branch_if => UncompiledFunction::insn_branch_if,
+        jit_insn_branch_if_not => UncompiledFunction::insn_branch_if_not,
+        jit_insn_jump_table => UncompiledFunction::insn_jump_table,
+        jit_insn_address_of => UncompiledFunction::insn_address_of,
+        jit_insn_address_of_label => UncompiledFunction::insn_address_of_label,
+        jit_insn_convert => UncompiledFunction::insn_convert,
+        jit_insn_call => UncompiledFunction::insn_call::<CompiledFunction>,
+        jit_insn_call_indirect => UncompiledFunction::insn_call_indirect,
+        jit_insn_call_indirect_vtable => UncompiledFunction::insn_call_indirect_vtable,
+        jit_insn_call_native => UncompiledFunction::insn_call_native,
+        jit_insn_call_intrinsic => UncompiledFunction::insn_call_intrinsic,
+        jit_insn_incoming_reg => UncompiledFunction::insn_incoming_reg,
+        jit_insn_incoming_frame_posn => UncompiledFunction::insn_incoming_frame_posn,
+        jit_insn_outgoing_reg => UncompiledFunction::insn_outgoing_reg,
+        jit_insn_outgoing_frame_posn => UncompiledFunction::insn_outgoing_frame_posn,
+        jit_insn_return_reg => UncompiledFunction::insn_return_reg,
+        jit_insn_setup_for_nested => UncompiledFunction::insn_setup_for_nested,
+        jit_insn_push_ptr => UncompiledFunction::insn_push_ptr,
+        jit_insn_set_param => UncompiledFunction::insn_set_param,
+        jit_insn_set_param_ptr => UncompiledFunction::insn_set_param_ptr,
+        jit_insn_push_return_area_ptr => UncompiledFunction::insn_push_return_area_ptr,
+        jit_insn_return => UncompiledFunction::insn_return,
+        jit_insn_return_ptr => UncompiledFunction::insn_return_ptr,
+        jit_insn_default_return => UncompiledFunction::insn_default_return,
+        jit_insn_throw => UncompiledFunction::insn_throw,
+        jit_insn_get_call_stack => UncompiledFunction::insn_get_call_stack,
+        jit_insn_thrown_exception => UncompiledFunction::insn_thrown_exception,
+        jit_insn_uses_catcher => UncompiledFunction::insn_uses_catcher,
+        jit_insn_start_catcher => UncompiledFunction::insn_start_catcher,
+        jit_insn_branch_if_pc_not_in_range => UncompiledFunction::insn_branch_if_pc_not_in_range,
+        jit_insn_rethrow_unhandled => UncompiledFunction::insn_rethrow_unhandled,
+        jit_insn_start_finally => UncompiledFunction::insn_start_finally,
+        jit_insn_return_from_finally => UncompiledFunction::insn_return_from_finally,
+        jit_insn_call_finally => UncompiledFunction::insn_call_finally,
+        jit_insn_start_filter => UncompiledFunction::insn_start_filter,
+        jit_insn_return_from_filter => UncompiledFunction::insn_return_from_filter,
+        jit_insn_call_filter => UncompiledFunction::insn_call_filter,
+        jit_insn_memcpy => UncompiledFunction::insn_memcpy,
+        jit_insn_memmove => UncompiledFunction::insn_memmove,
+        jit_insn_memset => UncompiledFunction::insn_memset,
+        jit_insn_alloca => UncompiledFunction::insn_alloca,
+        jit_insn_move_blocks_to_end => UncompiledFunction::insn_move_blocks_to_end,
+        jit_insn_move_blocks_to_start => UncompiledFunction::insn_move_blocks_to_start,
+        jit_insn_mark_offset => UncompiledFunction::insn_mark_offset,
+        jit_insn_mark_breakpoint => UncompiledFunction::insn_mark_breakpoint,
+        jit_insn_mark_breakpoint_variable => UncompiledFunction::insn_mark_breakpoint_variable,
+        jit_insn_iter_init => Block::iter,
+        jit_insn_iter_init_last => Block::iter_rev,
+        jit_insn_iter_next => <InstructionIter as Iterator>::next,
+        jit_insn_iter_previous => <RevInstructionIter as Iterator>::next
+    }
+}
@@ -58,17 +58,28 @@ extern crate "libjit-sys" as raw;
 use raw::*;
 use libc::c_void;
 use std::mem;
-pub use compile::Compile;
-pub use context::{Builder, Context};
+pub use closure::{Closure, ClosureUnsupported};
+pub use compile::{Compile, ExternFn};
+pub use context::{Builder, Context, ContextBuilder, ContextError, ContextOption};
+pub use debugger::Debugger;
+pub use dynamic::{ApplyError, DynValue};
 pub use elf::*;
-pub use function::{flags, Abi, AnyFunction, UncompiledFunction, Function, CompiledFunction};
+pub use exception::{BuiltinException, JitException};
+pub use function::{flags, Abi, AnyFunction, UncompiledFunction, Function, CompiledFunction, CallError, SignatureError};
 pub use function::flags::CallFlags;
+pub use insn::{Block, Blocks, Instruction, InstructionIter, Opcode, RevInstructionIter};
+pub use insns::INSN_FUNCTIONS;
 pub use label::Label;
+pub use memory::{CountingMemoryManager, DefaultMemoryManager, MemoryCounter, MemoryManager};
+pub use slice::{JitSlice, RawSlice};
+pub use tiered::TieredFunction;
+pub use trace::{Frame, Frames, StackTrace};
 pub use types::kind::TypeKind;
 pub use types::{kind, get, Type, Field, Fields, Params, CowType, StaticType, TypeRef, TaggedType};
 pub use types::consts as typecs;
 pub use util::NativeRef;
-pub use value::Value;
+pub use value::{FieldError, JitCmp, Value};
+pub use vmem::VMem;
 
 
 extern fn free_data<T>(data: *mut c_void) where T:'static {
@@ -92,6 +103,13 @@ pub fn uses_interpreter() -> bool {
         jit_uses_interpreter() != 0
     }
 }
+/// Check if the JIT supports closures
+#[inline]
+pub fn supports_closures() -> bool {
+    unsafe {
+        jit_supports_closures() != 0
+    }
+}
 /// Check if the JIT supports theads
 #[inline]
 pub fn supports_threads() -> bool {
@@ -108,12 +126,25 @@ pub fn supports_virtual_memory() -> bool {
 }
 #[macro_use]
 mod macros;
+mod closure;
 mod context;
 mod compile;
+pub mod debugger;
+#[cfg(all(feature = "disasm", target_arch = "x86_64"))]
+pub mod disasm;
+pub mod dynamic;
 mod elf;
+pub mod exception;
 mod function;
 mod insn;
+mod insns;
 mod label;
+pub mod memory;
+mod meta;
+mod slice;
+mod tiered;
+mod trace;
 mod types;
 mod util;
-mod value;
\ No newline at end of file
+mod value;
+pub mod vmem;
\ No newline at end of file
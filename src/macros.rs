@@ -61,6 +61,26 @@ macro_rules! compile_func(
         }
     )
 );
+macro_rules! extern_fn(
+    ($($ty:ident),* => $($name:ident),*) => (
+        impl<$($ty:Compile,)* R:Compile> ExternFn for extern fn($($ty),*) -> R {
+            type Args = ($($ty,)*);
+            type Output = R;
+            #[inline(always)]
+            unsafe fn from_code(code: *mut c_void) -> extern fn($($ty),*) -> R {
+                mem::transmute(code)
+            }
+            #[inline(always)]
+            fn as_code(self) -> *mut c_void {
+                unsafe { mem::transmute(self) }
+            }
+            unsafe fn apply(func: &CompiledFunction, ($(mut $name,)*): ($($ty,)*)) -> Result<R, JitException> {
+                let args: &mut [*mut c_void] = &mut [$(&mut $name as *mut $ty as *mut c_void),*];
+                apply_returning::<R>(func, args)
+            }
+        }
+    )
+);
 macro_rules! compile_tuple(
     ($($ty:ident),+ => $($name:ident),+) => (
         impl<$($ty),+> Compile for ($($ty),+) where $($ty:Compile),+ {
@@ -149,4 +169,70 @@ macro_rules! builtin_types(
     ($($c_name:ident -> $rust_name:ident);+) => (
         $(builtin_type!($c_name -> $rust_name);)+
     )
-);
\ No newline at end of file
+);
+macro_rules! insns(
+    (
+        unary { $($(#[$unary_attr:meta])* fn $unary:ident => $unary_c:ident;)* }
+        binary { $($(#[$binary_attr:meta])* fn $binary:ident => $binary_c:ident;)* }
+        statement { $($(#[$statement_attr:meta])* fn $statement:ident => $statement_c:ident;)* }
+        stack { $($(#[$stack_attr:meta])* fn $stack:ident => $stack_c:ident;)* }
+        manual { $($manual_c:ident => $manual:expr),* }
+    ) => (
+        impl<'a> UncompiledFunction<'a> {
+            $(
+                $(#[$unary_attr])*
+                #[inline(always)]
+                pub fn $unary(&self, value: Value<'a>) -> Value<'a> {
+                    unsafe {
+                        from_ptr($unary_c(self.as_ptr(), value.as_ptr()))
+                    }
+                }
+            )*
+            $(
+                $(#[$binary_attr])*
+                #[inline(always)]
+                pub fn $binary(&self, v1: Value<'a>, v2: Value<'a>) -> Value<'a> {
+                    unsafe {
+                        from_ptr($binary_c(self.as_ptr(), v1.as_ptr(), v2.as_ptr()))
+                    }
+                }
+            )*
+            $(
+                $(#[$statement_attr])*
+                #[inline(always)]
+                pub fn $statement(&self, value: Value<'a>) {
+                    unsafe {
+                        if $statement_c(self.as_ptr(), value.as_ptr()) == 0 {
+                            oom()
+                        }
+                    }
+                }
+            )*
+            $(
+                $(#[$stack_attr])*
+                #[inline(always)]
+                pub fn $stack(&self, num_items: isize) {
+                    unsafe {
+                        if $stack_c(self.as_ptr(), num_items as jit_nint) == 0 {
+                            oom()
+                        }
+                    }
+                }
+            )*
+        }
+        /// The name of every LibJIT instruction function that is wrapped
+        pub static INSN_FUNCTIONS: &'static [&'static str] = &[
+            $(stringify!($unary_c),)*
+            $(stringify!($binary_c),)*
+            $(stringify!($statement_c),)*
+            $(stringify!($stack_c),)*
+            $(stringify!($manual_c)),*
+        ];
+        /// Refer to the wrapper of each instruction function wrapped by hand,
+        /// so one can't be listed without existing
+        #[allow(dead_code)]
+        fn manual_wrappers() {
+            $(let _ = $manual;)*
+        }
+    )
+);
@@ -0,0 +1,395 @@
+//! Control over where the code cache of a context lives
+use raw::*;
+use context::Context;
+use libc::{c_int, c_void};
+use meta;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::ptr;
+use util::NativeRef;
+/// The result of `start_function` or `end_function` when it succeeded
+pub static MEMORY_OK: c_int = 0;
+/// The result of `start_function` or `end_function` when the function should
+/// be compiled again with more memory
+pub static MEMORY_RESTART: c_int = 1;
+/// The result of `start_function` or `end_function` when the function is too
+/// big to fit in memory
+pub static MEMORY_TOO_BIG: c_int = 2;
+/// The result of `start_function` or `end_function` when something else went
+/// wrong
+pub static MEMORY_ERROR: c_int = 3;
+/// Manages the memory that compiled code, functions, trampolines, closures
+/// and data are stored in for a context. The methods mirror LibJIT's
+/// `jit_memory_manager` table, and most managers wrap `DefaultMemoryManager`.
+///
+/// This is unsafe to implement because LibJIT trusts the memory it is given
+/// to be valid and, for code, executable.
+pub unsafe trait MemoryManager : 'static {
+    /// Prepare to manage memory for the context given, which is called the
+    /// first time the context needs memory
+    fn create(&mut self, ctx: jit_context_t);
+    /// Release all the memory, which is called when the context is destroyed
+    fn destroy(&mut self);
+    /// Find the information about the function containing the program counter
+    /// given
+    fn find_function_info(&mut self, pc: *mut c_void) -> jit_function_info_t;
+    /// Get the function the information given describes
+    fn get_function(&mut self, info: jit_function_info_t) -> jit_function_t;
+    /// Get the start of the code of the function the information given
+    /// describes
+    fn get_function_start(&mut self, info: jit_function_info_t) -> *mut c_void;
+    /// Get the end of the code of the function the information given
+    /// describes
+    fn get_function_end(&mut self, info: jit_function_info_t) -> *mut c_void;
+    /// Allocate a function
+    fn alloc_function(&mut self) -> jit_function_t;
+    /// Free a function
+    fn free_function(&mut self, func: jit_function_t);
+    /// Start writing the code of the function given, returning `MEMORY_OK` if
+    /// it can be written
+    fn start_function(&mut self, func: jit_function_t) -> c_int;
+    /// Finish writing the code of the current function, given the result of
+    /// compiling it
+    fn end_function(&mut self, result: c_int) -> c_int;
+    /// Make room for more code after `MEMORY_RESTART` was returned
+    fn extend_limit(&mut self, count: c_int) -> c_int;
+    /// Get the end of the memory the current function can be written to
+    fn get_limit(&mut self) -> *mut c_void;
+    /// Get the position the current function is being written at
+    fn get_break(&mut self) -> *mut c_void;
+    /// Set the position the current function is being written at
+    fn set_break(&mut self, brk: *mut c_void);
+    /// Allocate a trampoline
+    fn alloc_trampoline(&mut self) -> *mut c_void;
+    /// Free a trampoline
+    fn free_trampoline(&mut self, trampoline: *mut c_void);
+    /// Allocate a closure
+    fn alloc_closure(&mut self) -> *mut c_void;
+    /// Free a closure
+    fn free_closure(&mut self, closure: *mut c_void);
+    /// Allocate some data with the size and alignment given
+    fn alloc_data(&mut self, size: usize, align: usize) -> *mut c_void;
+}
+/// The memory manager of a context, kept in its metadata
+struct ManagerGlue(Box<MemoryManager>);
+unsafe fn manager<'a>(memory: jit_memory_context_t) -> &'a mut Box<MemoryManager> {
+    &mut *(memory as *mut Box<MemoryManager>)
+}
+extern fn glue_create(ctx: jit_context_t) -> jit_memory_context_t {
+    unsafe {
+        meta::with_context_map(ctx, |map| match map.get_mut::<ManagerGlue>() {
+            Some(glue) => {
+                glue.0.create(ctx);
+                &mut glue.0 as *mut Box<MemoryManager> as jit_memory_context_t
+            },
+            None => ptr::null_mut()
+        })
+    }
+}
+extern fn glue_destroy(memory: jit_memory_context_t) {
+    unsafe { manager(memory).destroy() }
+}
+extern fn glue_find_function_info(memory: jit_memory_context_t, pc: *mut c_void) -> jit_function_info_t {
+    unsafe { manager(memory).find_function_info(pc) }
+}
+extern fn glue_get_function(memory: jit_memory_context_t, info: jit_function_info_t) -> jit_function_t {
+    unsafe { manager(memory).get_function(info) }
+}
+extern fn glue_get_function_start(memory: jit_memory_context_t, info: jit_function_info_t) -> *mut c_void {
+    unsafe { manager(memory).get_function_start(info) }
+}
+extern fn glue_get_function_end(memory: jit_memory_context_t, info: jit_function_info_t) -> *mut c_void {
+    unsafe { manager(memory).get_function_end(info) }
+}
+extern fn glue_alloc_function(memory: jit_memory_context_t) -> jit_function_t {
+    unsafe { manager(memory).alloc_function() }
+}
+extern fn glue_free_function(memory: jit_memory_context_t, func: jit_function_t) {
+    unsafe { manager(memory).free_function(func) }
+}
+extern fn glue_start_function(memory: jit_memory_context_t, func: jit_function_t) -> c_int {
+    unsafe { manager(memory).start_function(func) }
+}
+extern fn glue_end_function(memory: jit_memory_context_t, result: c_int) -> c_int {
+    unsafe { manager(memory).end_function(result) }
+}
+extern fn glue_extend_limit(memory: jit_memory_context_t, count: c_int) -> c_int {
+    unsafe { manager(memory).extend_limit(count) }
+}
+extern fn glue_get_limit(memory: jit_memory_context_t) -> *mut c_void {
+    unsafe { manager(memory).get_limit() }
+}
+extern fn glue_get_break(memory: jit_memory_context_t) -> *mut c_void {
+    unsafe { manager(memory).get_break() }
+}
+extern fn glue_set_break(memory: jit_memory_context_t, brk: *mut c_void) {
+    unsafe { manager(memory).set_break(brk) }
+}
+extern fn glue_alloc_trampoline(memory: jit_memory_context_t) -> *mut c_void {
+    unsafe { manager(memory).alloc_trampoline() }
+}
+extern fn glue_free_trampoline(memory: jit_memory_context_t, trampoline: *mut c_void) {
+    unsafe { manager(memory).free_trampoline(trampoline) }
+}
+extern fn glue_alloc_closure(memory: jit_memory_context_t) -> *mut c_void {
+    unsafe { manager(memory).alloc_closure() }
+}
+extern fn glue_free_closure(memory: jit_memory_context_t, closure: *mut c_void) {
+    unsafe { manager(memory).free_closure(closure) }
+}
+extern fn glue_alloc_data(memory: jit_memory_context_t, size: jit_size_t, align: jit_size_t) -> *mut c_void {
+    unsafe { manager(memory).alloc_data(size as usize, align as usize) }
+}
+/// The table LibJIT calls into, which forwards to the `MemoryManager` in the
+/// context's metadata
+static GLUE: Struct_jit_memory_manager = Struct_jit_memory_manager {
+    create: Some(glue_create),
+    destroy: Some(glue_destroy),
+    find_function_info: Some(glue_find_function_info),
+    get_function: Some(glue_get_function),
+    get_function_start: Some(glue_get_function_start),
+    get_function_end: Some(glue_get_function_end),
+    alloc_function: Some(glue_alloc_function),
+    free_function: Some(glue_free_function),
+    start_function: Some(glue_start_function),
+    end_function: Some(glue_end_function),
+    extend_limit: Some(glue_extend_limit),
+    get_limit: Some(glue_get_limit),
+    get_break: Some(glue_get_break),
+    set_break: Some(glue_set_break),
+    alloc_trampoline: Some(glue_alloc_trampoline),
+    free_trampoline: Some(glue_free_trampoline),
+    alloc_closure: Some(glue_alloc_closure),
+    free_closure: Some(glue_free_closure),
+    alloc_data: Some(glue_alloc_data)
+};
+/// Find the end of the code of the function containing the program counter
+/// given, by asking the memory manager of the context given
+pub unsafe fn function_end(ctx: jit_context_t, pc: *mut c_void) -> Option<*mut c_void> {
+    meta::with_context_map(ctx, |map| match map.get_mut::<ManagerGlue>() {
+        Some(glue) => {
+            let info = glue.0.find_function_info(pc);
+            if info.is_null() {
+                None
+            } else {
+                Some(glue.0.get_function_end(info))
+            }
+        },
+        None => None
+    })
+}
+/// Make the context given use the memory manager given. This must happen
+/// before anything is built in the context.
+pub fn install(ctx: &Context, manager: Box<MemoryManager>) {
+    unsafe {
+        meta::with_context_map(ctx.as_ptr(), |map| map.insert(Box::new(ManagerGlue(manager))));
+        jit_context_set_memory_manager(ctx.as_ptr(), &GLUE);
+    }
+}
+/// LibJIT's own memory manager
+pub struct DefaultMemoryManager {
+    table: jit_memory_manager_t,
+    memory: jit_memory_context_t
+}
+impl DefaultMemoryManager {
+    #[inline(always)]
+    /// Make a wrapper around LibJIT's default memory manager
+    pub fn new() -> DefaultMemoryManager {
+        unsafe {
+            DefaultMemoryManager {
+                table: jit_default_memory_manager(),
+                memory: ptr::null_mut()
+            }
+        }
+    }
+    #[inline(always)]
+    /// Make a wrapper around the raw LibJIT memory manager given
+    ///
+    /// This is unsafe because the memory manager must be valid for as long as
+    /// the wrapper is used.
+    pub unsafe fn from_raw(table: jit_memory_manager_t) -> DefaultMemoryManager {
+        DefaultMemoryManager {
+            table: table,
+            memory: ptr::null_mut()
+        }
+    }
+}
+unsafe impl MemoryManager for DefaultMemoryManager {
+    fn create(&mut self, ctx: jit_context_t) {
+        unsafe {
+            self.memory = ((*self.table).create.unwrap())(ctx);
+        }
+    }
+    fn destroy(&mut self) {
+        unsafe {
+            ((*self.table).destroy.unwrap())(self.memory);
+            self.memory = ptr::null_mut();
+        }
+    }
+    fn find_function_info(&mut self, pc: *mut c_void) -> jit_function_info_t {
+        unsafe { ((*self.table).find_function_info.unwrap())(self.memory, pc) }
+    }
+    fn get_function(&mut self, info: jit_function_info_t) -> jit_function_t {
+        unsafe { ((*self.table).get_function.unwrap())(self.memory, info) }
+    }
+    fn get_function_start(&mut self, info: jit_function_info_t) -> *mut c_void {
+        unsafe { ((*self.table).get_function_start.unwrap())(self.memory, info) }
+    }
+    fn get_function_end(&mut self, info: jit_function_info_t) -> *mut c_void {
+        unsafe { ((*self.table).get_function_end.unwrap())(self.memory, info) }
+    }
+    fn alloc_function(&mut self) -> jit_function_t {
+        unsafe { ((*self.table).alloc_function.unwrap())(self.memory) }
+    }
+    fn free_function(&mut self, func: jit_function_t) {
+        unsafe { ((*self.table).free_function.unwrap())(self.memory, func) }
+    }
+    fn start_function(&mut self, func: jit_function_t) -> c_int {
+        unsafe { ((*self.table).start_function.unwrap())(self.memory, func) }
+    }
+    fn end_function(&mut self, result: c_int) -> c_int {
+        unsafe { ((*self.table).end_function.unwrap())(self.memory, result) }
+    }
+    fn extend_limit(&mut self, count: c_int) -> c_int {
+        unsafe { ((*self.table).extend_limit.unwrap())(self.memory, count) }
+    }
+    fn get_limit(&mut self) -> *mut c_void {
+        unsafe { ((*self.table).get_limit.unwrap())(self.memory) }
+    }
+    fn get_break(&mut self) -> *mut c_void {
+        unsafe { ((*self.table).get_break.unwrap())(self.memory) }
+    }
+    fn set_break(&mut self, brk: *mut c_void) {
+        unsafe { ((*self.table).set_break.unwrap())(self.memory, brk) }
+    }
+    fn alloc_trampoline(&mut self) -> *mut c_void {
+        unsafe { ((*self.table).alloc_trampoline.unwrap())(self.memory) }
+    }
+    fn free_trampoline(&mut self, trampoline: *mut c_void) {
+        unsafe { ((*self.table).free_trampoline.unwrap())(self.memory, trampoline) }
+    }
+    fn alloc_closure(&mut self) -> *mut c_void {
+        unsafe { ((*self.table).alloc_closure.unwrap())(self.memory) }
+    }
+    fn free_closure(&mut self, closure: *mut c_void) {
+        unsafe { ((*self.table).free_closure.unwrap())(self.memory, closure) }
+    }
+    fn alloc_data(&mut self, size: usize, align: usize) -> *mut c_void {
+        unsafe { ((*self.table).alloc_data.unwrap())(self.memory, size as jit_size_t, align as jit_size_t) }
+    }
+}
+/// A memory manager that counts the bytes of code and data allocated by
+/// another one, which is the default manager unless told otherwise
+pub struct CountingMemoryManager<M = DefaultMemoryManager> {
+    inner: M,
+    start: usize,
+    bytes: Arc<AtomicUsize>
+}
+impl CountingMemoryManager<DefaultMemoryManager> {
+    #[inline(always)]
+    /// Make a counting wrapper around LibJIT's default memory manager
+    pub fn new() -> CountingMemoryManager<DefaultMemoryManager> {
+        CountingMemoryManager::wrap(DefaultMemoryManager::new())
+    }
+}
+impl<M> CountingMemoryManager<M> where M:MemoryManager {
+    #[inline(always)]
+    /// Make a counting wrapper around the memory manager given
+    pub fn wrap(inner: M) -> CountingMemoryManager<M> {
+        CountingMemoryManager {
+            inner: inner,
+            start: 0,
+            bytes: Arc::new(AtomicUsize::new(0))
+        }
+    }
+    #[inline(always)]
+    /// Get a counter that can be read after this manager has been given to
+    /// a context
+    pub fn counter(&self) -> MemoryCounter {
+        MemoryCounter {
+            bytes: self.bytes.clone()
+        }
+    }
+}
+unsafe impl<M> MemoryManager for CountingMemoryManager<M> where M:MemoryManager {
+    fn create(&mut self, ctx: jit_context_t) {
+        self.inner.create(ctx)
+    }
+    fn destroy(&mut self) {
+        self.inner.destroy()
+    }
+    fn find_function_info(&mut self, pc: *mut c_void) -> jit_function_info_t {
+        self.inner.find_function_info(pc)
+    }
+    fn get_function(&mut self, info: jit_function_info_t) -> jit_function_t {
+        self.inner.get_function(info)
+    }
+    fn get_function_start(&mut self, info: jit_function_info_t) -> *mut c_void {
+        self.inner.get_function_start(info)
+    }
+    fn get_function_end(&mut self, info: jit_function_info_t) -> *mut c_void {
+        self.inner.get_function_end(info)
+    }
+    fn alloc_function(&mut self) -> jit_function_t {
+        self.inner.alloc_function()
+    }
+    fn free_function(&mut self, func: jit_function_t) {
+        self.inner.free_function(func)
+    }
+    fn start_function(&mut self, func: jit_function_t) -> c_int {
+        let result = self.inner.start_function(func);
+        self.start = self.inner.get_break() as usize;
+        result
+    }
+    fn end_function(&mut self, result: c_int) -> c_int {
+        let end = self.inner.get_break() as usize;
+        let result = self.inner.end_function(result);
+        if result == MEMORY_OK && end > self.start {
+            self.bytes.fetch_add(end - self.start, Ordering::SeqCst);
+        }
+        result
+    }
+    fn extend_limit(&mut self, count: c_int) -> c_int {
+        self.inner.extend_limit(count)
+    }
+    fn get_limit(&mut self) -> *mut c_void {
+        self.inner.get_limit()
+    }
+    fn get_break(&mut self) -> *mut c_void {
+        self.inner.get_break()
+    }
+    fn set_break(&mut self, brk: *mut c_void) {
+        self.inner.set_break(brk)
+    }
+    fn alloc_trampoline(&mut self) -> *mut c_void {
+        self.inner.alloc_trampoline()
+    }
+    fn free_trampoline(&mut self, trampoline: *mut c_void) {
+        self.inner.free_trampoline(trampoline)
+    }
+    fn alloc_closure(&mut self) -> *mut c_void {
+        self.inner.alloc_closure()
+    }
+    fn free_closure(&mut self, closure: *mut c_void) {
+        self.inner.free_closure(closure)
+    }
+    fn alloc_data(&mut self, size: usize, align: usize) -> *mut c_void {
+        let data = self.inner.alloc_data(size, align);
+        if !data.is_null() {
+            self.bytes.fetch_add(size, Ordering::SeqCst);
+        }
+        data
+    }
+}
+/// Reads the number of bytes a `CountingMemoryManager` has counted
+#[derive(Clone)]
+pub struct MemoryCounter {
+    bytes: Arc<AtomicUsize>
+}
+impl MemoryCounter {
+    #[inline(always)]
+    /// Get the number of bytes of code and data allocated so far
+    pub fn get(&self) -> usize {
+        self.bytes.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,105 @@
+use raw::*;
+use alloc::oom;
+use libc::{c_int, c_void};
+use std::any::{Any, TypeId};
+use std::boxed::BoxAny;
+use std::collections::HashMap;
+use std::mem;
+/// The metadata type that the typed metadata map of an object is stored
+/// under, which is below the types of 10000 and up that LibJIT keeps for itself
+static MAP_META: c_int = 9999;
+/// Holds at most one value of each type, so metadata of different types can
+/// never collide
+pub struct MetaMap {
+    values: HashMap<TypeId, Box<Any + 'static>>,
+    /// Values that were replaced or detached while they could still be
+    /// borrowed, which are only freed along with the map
+    retired: Vec<Box<Any + 'static>>
+}
+impl MetaMap {
+    /// Get the value of the type given
+    pub fn get<T>(&self) -> Option<&T> where T:'static {
+        self.values.get(&TypeId::of::<T>()).and_then(|value| value.downcast_ref())
+    }
+    /// Get the value of the type given mutably
+    pub fn get_mut<T>(&mut self) -> Option<&mut T> where T:'static {
+        self.values.get_mut(&TypeId::of::<T>()).and_then(|value| value.downcast_mut())
+    }
+    /// Set the value of the type given, dropping the old one
+    pub fn insert<T>(&mut self, data: Box<T>) where T:'static {
+        self.values.insert(TypeId::of::<T>(), data as Box<Any>);
+    }
+    /// Set the value of the type given, keeping the old one alive until the
+    /// map is dropped so references to it stay valid
+    pub fn replace<T>(&mut self, data: Box<T>) where T:'static {
+        if let Some(old) = self.values.insert(TypeId::of::<T>(), data as Box<Any>) {
+            self.retired.push(old);
+        }
+    }
+    /// Remove the value of the type given and return it
+    pub fn remove<T>(&mut self) -> Option<Box<T>> where T:'static {
+        self.values.remove(&TypeId::of::<T>()).and_then(|value| value.downcast().ok())
+    }
+    /// Remove the value of the type given, keeping it alive until the map is
+    /// dropped so references to it stay valid, and return true if there was
+    /// one
+    pub fn retire<T>(&mut self) -> bool where T:'static {
+        match self.values.remove(&TypeId::of::<T>()) {
+            Some(old) => {
+                self.retired.push(old);
+                true
+            },
+            None => false
+        }
+    }
+}
+unsafe fn with_map<G, S, F, R>(get: G, set: S, cb: F) -> R
+    where G:FnOnce(c_int) -> *mut c_void, S:FnOnce(c_int, *mut c_void) -> c_int, F:FnOnce(&mut MetaMap) -> R {
+    let mut map = get(MAP_META) as *mut MetaMap;
+    if map.is_null() {
+        map = mem::transmute(Box::new(MetaMap {
+            values: HashMap::new(),
+            retired: Vec::new()
+        }));
+        if set(MAP_META, map as *mut c_void) == 0 {
+            oom()
+        }
+    }
+    cb(&mut *map)
+}
+/// Run the closure given with the typed metadata map of a context, which
+/// lives as long as it does
+///
+/// This is unsafe because the map of the same context must not be used again
+/// until the closure returns.
+pub unsafe fn with_context_map<F, R>(ctx: jit_context_t, cb: F) -> R where F:FnOnce(&mut MetaMap) -> R {
+    with_map(
+        |key| jit_context_get_meta(ctx, key),
+        |key, map| jit_context_set_meta(ctx, key, map, Some(::free_data::<MetaMap>)),
+        cb
+    )
+}
+/// Run the closure given with the typed metadata map of a function, which
+/// lives as long as it does
+///
+/// This is unsafe because the map of the same function must not be used
+/// again until the closure returns.
+pub unsafe fn with_function_map<F, R>(func: jit_function_t, cb: F) -> R where F:FnOnce(&mut MetaMap) -> R {
+    with_map(
+        |key| jit_function_get_meta(func, key),
+        |key, map| jit_function_set_meta(func, key, map, Some(::free_data::<MetaMap>), 0),
+        cb
+    )
+}
+/// Run the closure given with the typed metadata map of a block, which lives
+/// as long as it does
+///
+/// This is unsafe because the map of the same block must not be used again
+/// until the closure returns.
+pub unsafe fn with_block_map<F, R>(block: jit_block_t, cb: F) -> R where F:FnOnce(&mut MetaMap) -> R {
+    with_map(
+        |key| jit_block_get_meta(block, key),
+        |key, map| jit_block_set_meta(block, key, map, Some(::free_data::<MetaMap>)),
+        cb
+    )
+}
@@ -0,0 +1,116 @@
+use compile::Compile;
+use function::UncompiledFunction;
+use std::marker::CovariantType;
+use types::get;
+use value::{JitCmp, Value};
+/// A slice in generated code, made of a pointer to its first element and its
+/// length, whose elements are of the type `T`
+///
+/// A `RawSlice<T>` passed as a parameter or compiled with `insn_of` becomes a
+/// value that `JitSlice::from_value` can unpack, as does a `&'static [T]`
+/// compiled with `insn_of`.
+#[derive(Copy)]
+pub struct JitSlice<'a, T> {
+    ptr: Value<'a>,
+    len: Value<'a>,
+    elem: CovariantType<T>
+}
+impl<'a, T> JitSlice<'a, T> where T:Compile {
+    /// Make a slice from a pointer to its first element and its length
+    pub fn new(ptr: Value<'a>, len: Value<'a>) -> JitSlice<'a, T> {
+        let func = ptr.get_function();
+        let nuint = get::<usize>();
+        JitSlice {
+            ptr: ptr,
+            len: func.insn_convert(len, nuint.get(), false),
+            elem: CovariantType::<T>
+        }
+    }
+    /// Unpack a slice from a value made from a `RawSlice<T>` or `&'static [T]`
+    pub fn from_value(value: Value<'a>) -> JitSlice<'a, T> {
+        let func = value.get_function();
+        let (ty, ptr_ty, nuint) = (get::<RawSlice<T>>(), get::<*mut T>(), get::<usize>());
+        let address = func.insn_address_of(value);
+        let mut offsets = ty.get().fields().map(|field| field.get_offset());
+        let ptr = func.insn_load_relative(address, offsets.next().unwrap(), ptr_ty.get());
+        let len = func.insn_load_relative(address, offsets.next().unwrap(), nuint.get());
+        JitSlice::new(ptr, len)
+    }
+    /// Get the pointer to the first element of this slice
+    pub fn get_ptr(&self) -> Value<'a> {
+        self.ptr
+    }
+    /// Get the number of elements in this slice
+    pub fn len(&self) -> Value<'a> {
+        self.len
+    }
+    fn get_function(&self) -> UncompiledFunction<'a> {
+        self.ptr.get_function()
+    }
+    /// Make instructions that load the element at the index given, throwing
+    /// `OutOfBounds` if the index isn't below the length of this slice
+    pub fn index(&self, index: Value<'a>) -> Value<'a> {
+        let elem = get::<T>();
+        self.get_function().insn_load_elem_checked(self.ptr, index, self.len, elem.get())
+    }
+    /// Make instructions that store a value as the element at the index
+    /// given, throwing `OutOfBounds` if the index isn't below the length of
+    /// this slice
+    pub fn index_set(&self, index: Value<'a>, value: Value<'a>) {
+        let func = self.get_function();
+        let elem = get::<T>();
+        let value = func.insn_convert(value, elem.get(), false);
+        func.insn_store_elem_checked(self.ptr, index, self.len, value)
+    }
+    /// Make a loop that runs the instructions the closure makes with each
+    /// index of this slice in turn
+    pub fn for_each_index<F>(&self, cb: F) where F:FnOnce(Value<'a>) {
+        let func = &self.get_function();
+        let nuint = get::<usize>();
+        let index = Value::new(func, nuint.get());
+        func.insn_store(index, func.insn_of(&0us));
+        func.insn_while(move || index.lt(self.len), move || {
+            cb(index);
+            func.insn_store(index, index + 1us);
+        });
+    }
+    /// Make a loop that runs the instructions the closure makes with each
+    /// element of this slice in turn
+    pub fn for_each<F>(&self, cb: F) where F:FnOnce(Value<'a>) {
+        let elem = get::<T>();
+        self.for_each_index(move |index| {
+            let func = self.get_function();
+            cb(func.insn_load_elem(self.ptr, index, elem.get()))
+        })
+    }
+}
+/// A slice laid out as a pointer to its first element followed by its
+/// length, which can be passed to and returned from compiled functions
+///
+/// Like a raw pointer, this doesn't keep the slice it was made from borrowed.
+#[repr(C)]
+pub struct RawSlice<T> {
+    ptr: *const T,
+    len: usize
+}
+impl<T> Copy for RawSlice<T> {}
+impl<T> RawSlice<T> {
+    #[inline(always)]
+    /// Make a raw slice that points to the slice given
+    pub fn new(slice: &[T]) -> RawSlice<T> {
+        RawSlice {
+            ptr: slice.as_ptr(),
+            len: slice.len()
+        }
+    }
+    #[inline(always)]
+    /// Get the pointer to the first element of this slice
+    pub fn as_ptr(&self) -> *const T {
+        self.ptr
+    }
+    #[inline(always)]
+    /// Get the number of elements in this slice
+    pub fn len(&self) -> usize {
+        self.len
+    }
+}
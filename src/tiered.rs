@@ -0,0 +1,107 @@
+use raw::*;
+use context::Context;
+use function::{flags, CompiledFunction, UncompiledFunction};
+use meta;
+use std::{intrinsics, ptr};
+use std::rt::unwind;
+use std::ops::Deref;
+use types::{get, TypeRef};
+use util::{from_ptr, NativeRef};
+type BuildFn = Box<Fn(&UncompiledFunction) + 'static>;
+struct TierState {
+    calls: usize,
+    threshold: usize,
+    promoted: bool,
+    func: jit_function_t,
+    build: BuildFn
+}
+extern fn promote(state: *mut u8) {
+    unsafe {
+        let outcome = unwind::try(|| {
+            let state = &mut *(state as *mut TierState);
+            let func:CompiledFunction = from_ptr(state.func);
+            let level = UncompiledFunction::get_max_optimization_level();
+            let build = &state.build;
+            // the call that got here is still running the old code, which
+            // LibJIT keeps around when a recompilable function is recompiled
+            state.promoted = func.recompile(|func| {
+                func.set_optimization_level(level);
+                build(func)
+            });
+        });
+        if outcome.is_err() {
+            // unwinding into the compiled code that called this is undefined
+            intrinsics::abort()
+        }
+    }
+}
+/// A function that is first compiled quickly with a call counter in its
+/// prologue, then rebuilt and recompiled at the maximum optimization level
+/// once it has been called enough times
+///
+/// The call counter isn't atomic, so the function must only be called from
+/// one thread at a time. The process is aborted if the closure that builds
+/// the function panics while it is being promoted.
+pub struct TieredFunction<'a> {
+    func: CompiledFunction<'a>,
+    state: *mut TierState
+}
+impl<'a> TieredFunction<'a> {
+    /// Make a function in the context given with the signature given, whose
+    /// body is built by the closure given, that is promoted to the maximum
+    /// optimization level after being called `threshold` times
+    pub fn new<F>(ctx: &'a mut Context, signature: TypeRef, threshold: usize, build: F) -> TieredFunction<'a>
+        where F:Fn(&UncompiledFunction) + 'static {
+        let mut state = Box::new(TierState {
+            calls: 0,
+            threshold: threshold,
+            promoted: false,
+            func: ptr::null_mut(),
+            build: Box::new(build)
+        });
+        let state_ptr = &mut *state as *mut TierState;
+        let func = ctx.build_func(signature, |func| {
+            func.set_recompilable();
+            func.set_optimization_level(0);
+            let counter = func.insn_of(&(&mut state.calls as *mut usize));
+            let calls = func.insn_load_relative(counter, 0, get::<usize>().get()) + func.insn_of(&1us);
+            func.insn_store_relative(counter, 0, calls);
+            func.insn_if(func.insn_eq(calls, func.insn_of(&state.threshold)), || {
+                let state_value = func.insn_of(&(state_ptr as *mut u8));
+                func.insn_call_rust(Some("promote"), promote as extern fn(*mut u8), &[state_value], flags::NO_THROW).unwrap();
+            });
+            (state.build)(func);
+        });
+        unsafe {
+            state.func = func.as_ptr();
+            meta::with_function_map(func.as_ptr(), |map| map.insert(state));
+        }
+        TieredFunction {
+            func: func,
+            state: state_ptr
+        }
+    }
+    #[inline(always)]
+    /// Get the number of times this function has been called before it was
+    /// promoted
+    pub fn get_calls(&self) -> usize {
+        unsafe { (*self.state).calls }
+    }
+    #[inline(always)]
+    /// Get the number of calls after which this function is promoted
+    pub fn get_threshold(&self) -> usize {
+        unsafe { (*self.state).threshold }
+    }
+    #[inline(always)]
+    /// Check if this function has been promoted to the maximum optimization
+    /// level
+    pub fn is_promoted(&self) -> bool {
+        unsafe { (*self.state).promoted }
+    }
+}
+impl<'a> Deref for TieredFunction<'a> {
+    type Target = CompiledFunction<'a>;
+    fn deref(&self) -> &CompiledFunction<'a> {
+        &self.func
+    }
+}
@@ -0,0 +1,171 @@
+use raw::*;
+use context::Context;
+use function::{AnyFunction, Function};
+use libc::{c_uint, c_void};
+use std::marker::ContravariantLifetime;
+use std::{mem, ptr};
+use util::{from_ptr, NativeRef};
+/// The offset LibJIT gives when no offset was marked for a program counter
+static NO_OFFSET: c_uint = !0;
+/// A single frame of a call stack
+pub struct Frame<'a> {
+    /// The function this frame is running, if it is a JIT function
+    pub function: Option<AnyFunction<'a>>,
+    /// The program counter of this frame
+    pub pc: *mut c_void,
+    /// The offset that was marked with `insn_mark_offset` before the
+    /// instruction this frame is running, if there is one
+    pub offset: Option<u32>
+}
+impl<'a> Frame<'a> {
+    /// Get the metadata of the type given that is attached to the function
+    /// of this frame, which is where a frontend can keep what it needs to
+    /// turn the offset of this frame into a source location
+    pub fn get_meta<T>(&self) -> Option<&T> where T:'static {
+        match self.function {
+            Some(ref func) => func.get_meta(),
+            None => None
+        }
+    }
+    fn new(function: jit_function_t, pc: *mut c_void, offset: c_uint) -> Frame<'a> {
+        unsafe {
+            Frame {
+                function: from_ptr(function),
+                pc: pc,
+                offset: if offset == NO_OFFSET { None } else { Some(offset as u32) }
+            }
+        }
+    }
+}
+/// Find the offset marked by `insn_mark_offset` for the code at the PC given
+/// in the compiled function given
+pub unsafe fn bytecode_offset(func: jit_function_t, pc: *mut c_void) -> Option<u32> {
+    let ctx = jit_function_get_context(func);
+    if jit_function_from_pc(ctx, pc, ptr::null_mut()) != func {
+        return None
+    }
+    // unwind from a frame that returns to the PC, so it is resolved the same
+    // way as a frame of a live call stack
+    let mut frame = _jit_arch_frame_t {
+        next_frame: ptr::null_mut(),
+        return_address: pc
+    };
+    let mut unwind = jit_unwind_context_t {
+        frame: &mut frame as *mut _jit_arch_frame_t as *mut c_void,
+        cache: ptr::null_mut(),
+        context: ctx
+    };
+    let offset = jit_unwind_get_offset(&mut unwind);
+    if offset == NO_OFFSET {
+        None
+    } else {
+        Some(offset as u32)
+    }
+}
+/// A snapshot of the call stack, which is normally used to indicate where an
+/// exception happened
+native_ref!(StackTrace {
+    _trace: jit_stack_trace_t
+});
+impl StackTrace {
+    /// Capture the current call stack, or return `None` if LibJIT can't
+    pub fn capture() -> Option<StackTrace> {
+        unsafe {
+            from_ptr(jit_exception_get_stack_trace())
+        }
+    }
+    #[inline]
+    /// Get the number of frames in this stack trace
+    pub fn len(&self) -> usize {
+        unsafe {
+            jit_stack_trace_get_size(self.as_ptr()) as usize
+        }
+    }
+    #[inline]
+    /// Get the program counter of the frame at the index given
+    pub fn get_pc(&self, index: usize) -> Option<*mut c_void> {
+        unsafe {
+            let pc = jit_stack_trace_get_pc(self.as_ptr(), index as c_uint);
+            if pc.is_null() {
+                None
+            } else {
+                Some(pc)
+            }
+        }
+    }
+    /// Iterate through the frames of this stack trace, resolving them to
+    /// functions in the context given
+    pub fn frames<'a>(&'a self, ctx: &'a Context) -> Frames<'a> {
+        unsafe {
+            Frames {
+                context: ctx.as_ptr(),
+                trace: self.as_ptr(),
+                index: 0,
+                length: jit_stack_trace_get_size(self.as_ptr()),
+                marker: ContravariantLifetime::<'a>
+            }
+        }
+    }
+}
+#[unsafe_destructor]
+impl Drop for StackTrace {
+    #[inline(always)]
+    fn drop(&mut self) {
+        unsafe {
+            jit_stack_trace_free(self.as_ptr());
+        }
+    }
+}
+/// Iterates through the frames of a stack trace
+pub struct Frames<'a> {
+    context: jit_context_t,
+    trace: jit_stack_trace_t,
+    index: c_uint,
+    length: c_uint,
+    marker: ContravariantLifetime<'a>
+}
+impl<'a> Iterator for Frames<'a> {
+    type Item = Frame<'a>;
+    fn next(&mut self) -> Option<Frame<'a>> {
+        if self.index < self.length {
+            let index = self.index;
+            self.index += 1;
+            unsafe {
+                Some(Frame::new(
+                    jit_stack_trace_get_function(self.context, self.trace, index),
+                    jit_stack_trace_get_pc(self.trace, index),
+                    jit_stack_trace_get_offset(self.context, self.trace, index)
+                ))
+            }
+        } else {
+            None
+        }
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        ((self.length - self.index) as usize, None)
+    }
+}
+/// Walk up the live call stack from the caller, resolving frames to functions
+/// in the context given, until the callback returns false or there are no
+/// frames left
+#[inline(never)]
+pub fn unwind<'a, F>(ctx: &'a Context, mut cb: F) where F:FnMut(Frame<'a>) -> bool {
+    unsafe {
+        let mut unwind:jit_unwind_context_t = mem::zeroed();
+        if jit_unwind_init(&mut unwind, ctx.as_ptr()) == 0 {
+            return;
+        }
+        loop {
+            let frame = Frame::new(
+                jit_unwind_get_function(&mut unwind),
+                jit_unwind_get_pc(&mut unwind),
+                jit_unwind_get_offset(&mut unwind)
+            );
+            if !cb(frame) || jit_unwind_next(&mut unwind) == 0 {
+                break;
+            }
+        }
+        jit_unwind_free(&mut unwind);
+    }
+}
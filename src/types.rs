@@ -396,6 +396,13 @@ impl<'a> TypeRef<'a> {
         }
     }
     #[inline(always)]
+    /// Get the application binary interface of this function type.
+    pub fn get_abi(self) -> Abi {
+        unsafe {
+            mem::transmute(jit_type_get_abi(self.as_ptr()))
+        }
+    }
+    #[inline(always)]
     /// Get the type returned by this function type.
     pub fn get_return(self) -> Option<TypeRef<'a>> {
         unsafe {
@@ -435,6 +442,12 @@ impl<'a> TypeRef<'a> {
             }
         }
     }
+    #[inline]
+    /// Find the field with a particular name, or `None` if this type has no
+    /// field with that name.
+    pub fn find_field(self, name:&str) -> Option<Field<'a>> {
+        self.fields().find(|field| field.get_name() == Some(name))
+    }
     #[inline(always)]
     /// Check if this is a pointer
     pub fn is_primitive(self) -> bool {
@@ -477,6 +490,33 @@ impl<'a> TypeRef<'a> {
             jit_type_is_tagged(self.as_ptr()) != 0
         }
     }
+    /// Check if values of this type are represented the same way as values
+    /// of the other type, so one can be passed where the other is expected
+    ///
+    /// Structs and unions must have compatible fields at the same offsets,
+    /// and signatures must have the same ABI.
+    pub fn is_compatible(self, other: TypeRef) -> bool {
+        if self.is_signature() || other.is_signature() {
+            self.is_signature() && other.is_signature()
+                && self.get_abi() == other.get_abi()
+                && match (self.get_return(), other.get_return()) {
+                    (Some(a), Some(b)) => a.is_compatible(b),
+                    (None, None) => true,
+                    _ => false
+                }
+                && self.params().count() == other.params().count()
+                && self.params().zip(other.params()).all(|(a, b)| a.is_compatible(*b))
+        } else {
+            unsafe {
+                let this:TypeRef = from_ptr(jit_type_normalize(self.as_ptr()));
+                let other:TypeRef = from_ptr(jit_type_normalize(other.as_ptr()));
+                this.get_kind() == other.get_kind() && this.get_size() == other.get_size()
+                    && this.fields().count() == other.fields().count()
+                    && this.fields().zip(other.fields()).all(|(a, b)|
+                        a.get_offset() == b.get_offset() && a.get_type().is_compatible(*b.get_type()))
+            }
+        }
+    }
 }
 impl<'a> IntoIterator for TypeRef<'a> {
     type IntoIter = Fields<'a>;
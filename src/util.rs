@@ -30,18 +30,28 @@ impl<T> NativeRef for Option<T> where T:NativeRef {
     }
 }
 
+/// Collect the output LibJIT writes to a file in the callback given. It is
+/// written to a temporary file first, so no amount of output can block.
 pub fn dump<F>(cb: F) -> Result<String, Error> where F:FnOnce(*mut FILE) {
-    use std::old_io::pipe::PipeStream;
-    use std::os;
-    use libc::{fdopen, fclose};
+    use libc::{fclose, fflush, fread, rewind, size_t, tmpfile};
     unsafe {
-        let pair = os::pipe().unwrap();
-        let file = fdopen(pair.writer, b"w".as_ptr() as *const i8);
+        let file = tmpfile();
+        if file.is_null() {
+            return Err(Error)
+        }
         cb(file);
-        fclose(file);
-        match PipeStream::open(pair.reader).read_to_end() {
-            Ok(v) => Ok(String::from_utf8_unchecked(v)),
-            Err(_) => Err(Error)
+        fflush(file);
+        rewind(file);
+        let mut bytes = Vec::new();
+        let mut buffer = [0u8; 4096];
+        loop {
+            let read = fread(buffer.as_mut_ptr() as *mut c_void, 1, buffer.len() as size_t, file);
+            if read == 0 {
+                break;
+            }
+            bytes.push_all(&buffer[..read as usize]);
         }
+        fclose(file);
+        String::from_utf8(bytes).map_err(|_| Error)
     }
-}
\ No newline at end of file
+}
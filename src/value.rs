@@ -1,10 +1,37 @@
 use raw::*;
 use function::UncompiledFunction;
 use std::marker::ContravariantLifetime;
-use std::fmt;
+use std::error::Error;
+use std::{fmt, mem, ptr};
 use std::ops::*;
 use types::*;
-use util::NativeRef;
+use util::{self, from_ptr, NativeRef};
+/// An error from accessing a field of a value by name
+#[derive(Debug, PartialEq)]
+pub enum FieldError {
+    /// The value is neither a struct nor a pointer to one
+    NotStruct,
+    /// The struct has no field with this name
+    UnknownField(String)
+}
+impl fmt::Display for FieldError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FieldError::NotStruct =>
+                write!(fmt, "value is not a struct or a pointer to a struct"),
+            FieldError::UnknownField(ref name) =>
+                write!(fmt, "struct has no field named `{}`", name)
+        }
+    }
+}
+impl Error for FieldError {
+    fn description(&self) -> &str {
+        match *self {
+            FieldError::NotStruct => "Value is not a struct",
+            FieldError::UnknownField(_) => "Struct has no field with that name"
+        }
+    }
+}
 /// Values form the backbone of the storage system in `libjit`.
 /// Every value in the system, be it a constant, a local variable, or a
 /// temporary result, is represented by an object of type `Value`. The JIT then
@@ -30,8 +57,37 @@ impl<'a> NativeRef for Value<'a> {
     }
 }
 impl<'a> fmt::Display for Value<'a> {
+    /// Write constants as their literal value, parameters as `arg` followed
+    /// by their index, temporaries as `t` followed by their address and
+    /// locals as `l` followed by their address
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmt, "v({})", self.get_type())
+        unsafe {
+            let value = self.as_ptr();
+            if self.is_constant() {
+                let ty:TypeRef = from_ptr(jit_type_remove_tags(jit_value_get_type(value)));
+                let kind = ty.get_kind();
+                if kind == kind::Float32 {
+                    write!(fmt, "{}", jit_value_get_float32_constant(value))
+                } else if kind == kind::Float64 {
+                    write!(fmt, "{}", jit_value_get_float64_constant(value))
+                } else if kind == kind::NFloat {
+                    write!(fmt, "{}", jit_value_get_nfloat_constant(value) as f64)
+                } else if kind == kind::Long {
+                    write!(fmt, "{}", jit_value_get_long_constant(value))
+                } else if kind == kind::ULong {
+                    write!(fmt, "{}", jit_value_get_long_constant(value) as u64)
+                } else if kind == kind::Pointer || kind == kind::Signature {
+                    write!(fmt, "{:p}", jit_value_get_nint_constant(value) as *const u8)
+                } else {
+                    write!(fmt, "{}", jit_value_get_nint_constant(value))
+                }
+            } else if let Some(index) = self.get_param_index() {
+                write!(fmt, "arg{}", index)
+            } else {
+                let prefix = if self.is_temp() { "t" } else { "l" };
+                write!(fmt, "{}{:x}", prefix, value as usize)
+            }
+        }
     }
 }
 impl<'a> Clone for Value<'a> {
@@ -75,6 +131,101 @@ impl<'a> Value<'a> {
             jit_value_is_temporary(self.as_ptr()) != 0
         }
     }
+    /// Determine if a value is local.  i.e. its scope extends over multiple
+    /// blocks within its function.
+    #[inline]
+    pub fn is_local(&self) -> bool {
+        unsafe {
+            jit_value_is_local(self.as_ptr()) != 0
+        }
+    }
+    /// Determine if a value is a constant.
+    #[inline]
+    pub fn is_constant(&self) -> bool {
+        unsafe {
+            jit_value_is_constant(self.as_ptr()) != 0
+        }
+    }
+    /// Determine if a value is a parameter of its function.
+    #[inline]
+    pub fn is_parameter(&self) -> bool {
+        unsafe {
+            jit_value_is_parameter(self.as_ptr()) != 0
+        }
+    }
+    /// Get the index of the parameter this value is, if it is one
+    pub fn get_param_index(&self) -> Option<usize> {
+        if !self.is_parameter() {
+            return None
+        }
+        unsafe {
+            let func = jit_value_get_function(self.as_ptr());
+            let num_params = jit_type_num_params(jit_function_get_signature(func));
+            range(0, num_params).find(|&index| jit_value_get_param(func, index) == self.as_ptr())
+                .map(|index| index as usize)
+        }
+    }
+    /// Get the address and type of the struct this value is or points to
+    fn get_struct(&self) -> Result<(Value<'a>, TypeRef<'a>), FieldError> {
+        unsafe {
+            let ty:TypeRef<'a> = from_ptr(jit_type_remove_tags(jit_value_get_type(self.as_ptr())));
+            let struct_ty:TypeRef<'a> = if ty.is_pointer() {
+                from_ptr(jit_type_remove_tags(jit_type_get_ref(ty.as_ptr())))
+            } else {
+                ty
+            };
+            if !struct_ty.is_struct() && !struct_ty.is_union() {
+                Err(FieldError::NotStruct)
+            } else if ty.is_pointer() {
+                Ok((*self, struct_ty))
+            } else {
+                Ok((self.get_function().insn_address_of(*self), struct_ty))
+            }
+        }
+    }
+    /// Find the offset and type of the field with the name given in the
+    /// struct type given
+    fn find_field(ty: TypeRef<'a>, name: &str) -> Result<(usize, TypeRef<'a>), FieldError> {
+        match ty.find_field(name) {
+            Some(field) => unsafe {
+                Ok((field.get_offset(), from_ptr(jit_type_get_field(ty.as_ptr(), field.index))))
+            },
+            None => Err(FieldError::UnknownField(name.to_string()))
+        }
+    }
+    /// Make an instruction that loads the field with the name given from the
+    /// struct this value is or points to.
+    ///
+    /// This returns an error without making the instruction if this value
+    /// isn't a struct or a pointer to one, or if the struct has no field with
+    /// that name.
+    pub fn field(&self, name: &str) -> Result<Value<'a>, FieldError> {
+        let (address, ty) = try!(self.get_struct());
+        let (offset, field_ty) = try!(Value::find_field(ty, name));
+        Ok(self.get_function().insn_load_relative(address, offset, field_ty))
+    }
+    /// Make an instruction that stores a value in the field with the name
+    /// given of the struct this value is or points to, converting it to the
+    /// type of the field.
+    ///
+    /// This returns an error without making the instruction if this value
+    /// isn't a struct or a pointer to one, or if the struct has no field with
+    /// that name.
+    pub fn set_field(&self, name: &str, value: Value<'a>) -> Result<(), FieldError> {
+        let (address, ty) = try!(self.get_struct());
+        let (offset, field_ty) = try!(Value::find_field(ty, name));
+        let func = self.get_function();
+        let value = func.insn_convert(value, field_ty, false);
+        func.insn_store_relative(address, offset, value);
+        Ok(())
+    }
+    /// Dump this value the way LibJIT would, with its type
+    pub fn dump(&self) -> Result<String, fmt::Error> {
+        util::dump(|fd| unsafe {
+            let func = jit_value_get_function(self.as_ptr());
+            jit_dump_value(mem::transmute(fd), func, self.as_ptr(), ptr::null());
+        })
+    }
     /// Determine if a value is addressable.
     #[inline]
     pub fn is_addressable(&self) -> bool {
@@ -101,6 +252,18 @@ macro_rules! bin_op {
                 self.get_function().$func(self, other)
             }
         }
+        bin_op!{$trait_ty, $trait_func, $func: isize, usize, i64, u64, i32, u32, i16, u16, i8, u8, f64, f32}
+    );
+    ($trait_ty:ident, $trait_func:ident, $func:ident: $($ty:ty),+) => (
+        $(
+            impl<'a> $trait_ty<$ty> for Value<'a> {
+                type Output = Value<'a>;
+                fn $trait_func(self, other: $ty) -> Value<'a> {
+                    let func = self.get_function();
+                    func.$func(self, func.insn_of(&other))
+                }
+            }
+        )+
     )
 }
 macro_rules! un_op {
@@ -124,4 +287,76 @@ bin_op!{Shl, shl, insn_shl}
 bin_op!{Shr, shr, insn_shr}
 bin_op!{Sub, sub, insn_sub}
 un_op!{Neg, neg, insn_neg}
-un_op!{Not, not, insn_not}
\ No newline at end of file
+un_op!{Not, not, insn_not}
+/// Comparisons that make instructions giving their result as a `Value`, since
+/// `PartialEq` and `PartialOrd` have to give a `bool`
+pub trait JitCmp<'a, Rhs> {
+    /// Make an instruction that checks if this value is lower than the other
+    fn lt(self, other: Rhs) -> Value<'a>;
+    /// Make an instruction that checks if this value is lower than or equal
+    /// to the other
+    fn le(self, other: Rhs) -> Value<'a>;
+    /// Make an instruction that checks if this value is greater than the
+    /// other
+    fn gt(self, other: Rhs) -> Value<'a>;
+    /// Make an instruction that checks if this value is greater than or
+    /// equal to the other
+    fn ge(self, other: Rhs) -> Value<'a>;
+    /// Make an instruction that checks if the values are equal
+    fn eq_v(self, other: Rhs) -> Value<'a>;
+    /// Make an instruction that checks if the values are not equal
+    fn ne_v(self, other: Rhs) -> Value<'a>;
+}
+macro_rules! cmp_ops {
+    ($($ty:ty),+) => (
+        $(
+            impl<'a> JitCmp<'a, $ty> for Value<'a> {
+                fn lt(self, other: $ty) -> Value<'a> {
+                    let func = self.get_function();
+                    func.insn_lt(self, func.insn_of(&other))
+                }
+                fn le(self, other: $ty) -> Value<'a> {
+                    let func = self.get_function();
+                    func.insn_leq(self, func.insn_of(&other))
+                }
+                fn gt(self, other: $ty) -> Value<'a> {
+                    let func = self.get_function();
+                    func.insn_gt(self, func.insn_of(&other))
+                }
+                fn ge(self, other: $ty) -> Value<'a> {
+                    let func = self.get_function();
+                    func.insn_geq(self, func.insn_of(&other))
+                }
+                fn eq_v(self, other: $ty) -> Value<'a> {
+                    let func = self.get_function();
+                    func.insn_eq(self, func.insn_of(&other))
+                }
+                fn ne_v(self, other: $ty) -> Value<'a> {
+                    let func = self.get_function();
+                    func.insn_neq(self, func.insn_of(&other))
+                }
+            }
+        )+
+    )
+}
+impl<'a> JitCmp<'a, Value<'a>> for Value<'a> {
+    fn lt(self, other: Value<'a>) -> Value<'a> {
+        self.get_function().insn_lt(self, other)
+    }
+    fn le(self, other: Value<'a>) -> Value<'a> {
+        self.get_function().insn_leq(self, other)
+    }
+    fn gt(self, other: Value<'a>) -> Value<'a> {
+        self.get_function().insn_gt(self, other)
+    }
+    fn ge(self, other: Value<'a>) -> Value<'a> {
+        self.get_function().insn_geq(self, other)
+    }
+    fn eq_v(self, other: Value<'a>) -> Value<'a> {
+        self.get_function().insn_eq(self, other)
+    }
+    fn ne_v(self, other: Value<'a>) -> Value<'a> {
+        self.get_function().insn_neq(self, other)
+    }
+}
+cmp_ops!{isize, usize, i64, u64, i32, u32, i16, u16, i8, u8, f64, f32}
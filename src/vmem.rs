@@ -0,0 +1,162 @@
+//! Portable virtual memory, for making regions with guard pages around them
+use raw::*;
+use libc::c_void;
+use std::num;
+pub use self::flags::Protection;
+pub mod flags {
+    use libc::c_uint;
+    /// How a region of memory can be accessed
+    bitflags!(
+        flags Protection: c_uint {
+            /// When the memory can be read
+            const READ = 1,
+            /// When the memory can be written
+            const WRITE = 2,
+            /// When the memory can be executed
+            const EXEC = 4
+        }
+    );
+}
+impl Protection {
+    fn as_prot(self) -> jit_prot_t {
+        if self.contains(flags::EXEC) {
+            if self.contains(flags::WRITE) {
+                JIT_PROT_EXEC_READ_WRITE
+            } else {
+                JIT_PROT_EXEC_READ
+            }
+        } else if self.contains(flags::WRITE) {
+            JIT_PROT_READ_WRITE
+        } else if self.contains(flags::READ) {
+            JIT_PROT_READ
+        } else {
+            JIT_PROT_NONE
+        }
+    }
+}
+#[inline(always)]
+/// Get the size of a page of virtual memory
+pub fn page_size() -> usize {
+    unsafe {
+        jit_vmem_init();
+        jit_vmem_page_size() as usize
+    }
+}
+#[inline(always)]
+/// Round the value given up to a multiple of the page size
+pub fn round_up(value: usize) -> usize {
+    unsafe {
+        jit_vmem_init();
+        jit_vmem_round_up(value as jit_nuint) as usize
+    }
+}
+#[inline(always)]
+/// Round the value given down to a multiple of the page size
+pub fn round_down(value: usize) -> usize {
+    unsafe {
+        jit_vmem_init();
+        jit_vmem_round_down(value as jit_nuint) as usize
+    }
+}
+#[inline(always)]
+/// Convert the size given to what LibJIT takes, or `None` if it is too big
+fn jit_size(size: usize) -> Option<jit_uint> {
+    num::cast(size)
+}
+/// A region of virtual memory, which is released when it is dropped
+pub struct VMem {
+    addr: *mut u8,
+    size: jit_uint
+}
+impl VMem {
+    /// Reserve a region of at least the size given without committing any
+    /// memory to it, so it can't be accessed until parts of it are committed
+    ///
+    /// This gives `None` if the size is too big for LibJIT.
+    pub fn reserve(size: usize) -> Option<VMem> {
+        let size = match jit_size(round_up(size)) {
+            Some(size) => size,
+            None => return None
+        };
+        unsafe {
+            VMem::from_raw(jit_vmem_reserve(size), size)
+        }
+    }
+    /// Reserve a region of at least the size given and commit all of it with
+    /// the protection given
+    ///
+    /// This gives `None` if the size is too big for LibJIT.
+    pub fn reserve_committed(size: usize, prot: Protection) -> Option<VMem> {
+        let size = match jit_size(round_up(size)) {
+            Some(size) => size,
+            None => return None
+        };
+        unsafe {
+            VMem::from_raw(jit_vmem_reserve_committed(size, prot.as_prot()), size)
+        }
+    }
+    fn from_raw(addr: *mut c_void, size: jit_uint) -> Option<VMem> {
+        if addr.is_null() {
+            None
+        } else {
+            Some(VMem {
+                addr: addr as *mut u8,
+                size: size
+            })
+        }
+    }
+    fn check_range(&self, offset: usize, size: usize) -> *mut c_void {
+        match offset.checked_add(size) {
+            Some(end) if offset % page_size() == 0 && end <= self.len() => (),
+            _ => panic!("Range of {} bytes at {} is not page-aligned inside the region", size, offset)
+        }
+        unsafe {
+            self.addr.offset(offset as isize) as *mut c_void
+        }
+    }
+    /// Commit memory to the part of the region at the offset given, which
+    /// must be page-aligned, returning true if it worked
+    pub fn commit(&mut self, offset: usize, size: usize, prot: Protection) -> bool {
+        let addr = self.check_range(offset, size);
+        match jit_size(size) {
+            Some(size) => unsafe { jit_vmem_commit(addr, size, prot.as_prot()) != 0 },
+            None => false
+        }
+    }
+    /// Give back the memory of the part of the region at the offset given,
+    /// which must be page-aligned, returning true if it worked
+    pub fn decommit(&mut self, offset: usize, size: usize) -> bool {
+        let addr = self.check_range(offset, size);
+        match jit_size(size) {
+            Some(size) => unsafe { jit_vmem_decommit(addr, size) != 0 },
+            None => false
+        }
+    }
+    /// Change the protection of the part of the region at the offset given,
+    /// which must be page-aligned, returning true if it worked
+    pub fn protect(&mut self, offset: usize, size: usize, prot: Protection) -> bool {
+        let addr = self.check_range(offset, size);
+        match jit_size(size) {
+            Some(size) => unsafe { jit_vmem_protect(addr, size, prot.as_prot()) != 0 },
+            None => false
+        }
+    }
+    #[inline(always)]
+    /// Get a pointer to the start of the region
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.addr
+    }
+    #[inline(always)]
+    /// Get the size of the region in bytes
+    pub fn len(&self) -> usize {
+        self.size as usize
+    }
+}
+impl Drop for VMem {
+    #[inline(always)]
+    fn drop(&mut self) {
+        unsafe {
+            jit_vmem_release(self.addr as *mut c_void, self.size);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+#![feature(test, plugin)]
+#![plugin(jit_macros)]
+#[no_link] #[macro_use]
+extern crate jit_macros;
+extern crate jit;
+use jit::*;
+
+#[test]
+fn test_call() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(isize, isize) -> isize>();
+    let func = ctx.build_func(sig.get(), |func| {
+        func.insn_return(func[0] * func[1]);
+    });
+    assert_eq!(func.call::<extern fn(isize, isize) -> isize>((4, 5)).unwrap(), 20);
+    let mul:extern fn(isize, isize) -> isize = unsafe { func.get_fn().unwrap() };
+    assert_eq!(mul(6, 7), 42);
+}
+
+#[test]
+fn test_call_mismatch() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(isize, isize) -> isize>();
+    let func = ctx.build_func(sig.get(), |func| {
+        func.insn_return(func[0] + func[1]);
+    });
+    assert!(unsafe { func.get_fn::<extern fn(f64) -> f64>() }.is_err());
+    match func.call::<extern fn(isize) -> isize>((1,)) {
+        Err(CallError::Signature(_)) => (),
+        _ => panic!("expected a signature error")
+    }
+}
+
+#[test]
+fn test_call_exception() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(isize, isize) -> isize>();
+    let func = ctx.build_func(sig.get(), |func| {
+        func.insn_return(func[0] / func[1]);
+    });
+    match func.call::<extern fn(isize, isize) -> isize>((1, 0)) {
+        Err(CallError::Exception(JitException::Builtin(BuiltinException::DivisionByZero))) => (),
+        _ => panic!("expected a division by zero")
+    }
+}
+
+#[test]
+fn test_call_rust() {
+    extern fn add(a: isize, b: isize) -> isize {
+        a + b
+    }
+    let mut ctx = Context::new();
+    let sig = get::<fn(isize) -> isize>();
+    let func = ctx.build_func(sig.get(), |func| {
+        let one = func.insn_of(&1is);
+        let sum = func.insn_call_rust(Some("add"), add as extern fn(isize, isize) -> isize, &[func[0], one], flags::NO_THROW);
+        func.insn_return(sum.unwrap());
+    });
+    assert_eq!(func.call::<extern fn(isize) -> isize>((41,)).unwrap(), 42);
+}
+
+#[test]
+fn test_call_rust_mismatch() {
+    extern fn add(a: isize, b: isize) -> isize {
+        a + b
+    }
+    let mut ctx = Context::new();
+    let sig = get::<fn(isize) -> isize>();
+    ctx.build_func(sig.get(), |func| {
+        let add = add as extern fn(isize, isize) -> isize;
+        assert!(func.insn_call_rust(Some("add"), add, &[func[0]], flags::NO_THROW).is_err());
+        assert!(func.insn_call_rust(Some("add"), add, &[func[0], func.insn_of(&1.0f64)], flags::NO_THROW).is_err());
+        func.insn_return(func[0]);
+    });
+}
+
+#[test]
+fn test_struct_compatibility() {
+    let (float, long) = (get::<f64>(), get::<i64>());
+    let floats = Type::new_struct(&mut [float.get()]);
+    let longs = Type::new_struct(&mut [long.get()]);
+    assert!(floats.is_compatible(*floats));
+    assert!(!floats.is_compatible(*longs));
+}
@@ -0,0 +1,55 @@
+#![feature(test, plugin)]
+#![plugin(jit_macros)]
+#[no_link] #[macro_use]
+extern crate jit_macros;
+extern crate jit;
+use jit::*;
+use std::cell::Cell;
+use std::rc::Rc;
+
+#[test]
+fn test_closure() {
+    if !supports_closures() {
+        return;
+    }
+    let mut ctx = Context::new();
+    let offset = 10;
+    let sig = get::<fn(isize) -> isize>();
+    ctx.build(|builder| {
+        let closure = Closure::new(builder, sig.get(), move |args| match args[0] {
+            DynValue::NInt(x) => DynValue::NInt(x + offset),
+            _ => panic!("expected a native integer")
+        }).unwrap();
+        let result = unsafe {
+            dynamic::apply(closure.get_signature(), closure.as_code(), &[DynValue::NInt(32)])
+        };
+        assert_eq!(result.unwrap(), DynValue::NInt(42));
+    });
+}
+
+#[test]
+fn test_call_closure() {
+    if !supports_closures() {
+        return;
+    }
+    let mut ctx = Context::new();
+    let calls = Rc::new(Cell::new(0));
+    let sig = get::<fn(isize) -> isize>();
+    let closure_calls = calls.clone();
+    ctx.build(|builder| {
+        let closure = Closure::new(builder, sig.get(), move |args| {
+            closure_calls.set(closure_calls.get() + 1);
+            match args[0] {
+                DynValue::NInt(x) => DynValue::NInt(x * 2),
+                _ => panic!("expected a native integer")
+            }
+        }).unwrap();
+        let func = UncompiledFunction::new(builder, sig.get());
+        let mut args = [func[0]];
+        let doubled = func.insn_call_closure(Some("double"), &closure, args.as_mut_slice(), flags::CallFlags::empty());
+        func.insn_return(doubled);
+        let func = func.compile();
+        assert_eq!(func.call::<extern fn(isize) -> isize>((21,)).unwrap(), 42);
+    });
+    assert_eq!(calls.get(), 1);
+}
@@ -0,0 +1,55 @@
+#![feature(test, plugin)]
+#![plugin(jit_macros)]
+#[no_link] #[macro_use]
+extern crate jit_macros;
+extern crate jit;
+extern crate "libjit-sys" as raw;
+use jit::*;
+
+#[test]
+fn test_context_builder() {
+    let mut ctx = ContextBuilder::new()
+        .cache_page_size(4096)
+        .cache_limit(1 << 20)
+        .dont_fold(true)
+        .build().unwrap();
+    assert_eq!(ctx.get_option(ContextOption::CachePageSize), 4096);
+    assert_eq!(ctx.get_option(ContextOption::CacheLimit), 1 << 20);
+    assert_eq!(ctx.get_option(ContextOption::DontFold), 1);
+    assert_eq!(ctx.get_option(ContextOption::PositionIndependent), 0);
+    let sig = get::<fn(isize) -> isize>();
+    let func = ctx.build_func(sig.get(), |func| {
+        func.insn_return(func[0] + func[0]);
+    });
+    assert_eq!(func.call::<extern fn(isize) -> isize>((21,)).unwrap(), 42);
+}
+
+#[test]
+fn test_context_builder_invalid() {
+    assert_eq!(ContextBuilder::new().cache_page_size(1000).build().err(),
+        Some(ContextError::PageSizeNotPowerOfTwo(1000)));
+    assert_eq!(ContextBuilder::new().cache_page_size(4096).cache_limit(1024).build().err(),
+        Some(ContextError::CacheLimitTooSmall { limit: 1024, page_size: 4096 }));
+    assert_eq!(ContextBuilder::new().cache_max_page_factor(0).build().err(),
+        Some(ContextError::ZeroPageFactor));
+    let builder = unsafe {
+        ContextBuilder::new()
+            .memory_manager(CountingMemoryManager::new())
+            .raw_memory_manager(raw::jit_default_memory_manager())
+    };
+    assert_eq!(builder.build().err(), Some(ContextError::ConflictingMemoryManagers));
+}
+
+#[test]
+fn test_counting_memory_manager() {
+    let manager = CountingMemoryManager::new();
+    let counter = manager.counter();
+    let mut ctx = ContextBuilder::new().memory_manager(manager).build().unwrap();
+    assert_eq!(counter.get(), 0);
+    let sig = get::<fn(isize) -> isize>();
+    let func = ctx.build_func(sig.get(), |func| {
+        func.insn_return(func[0] * func[0]);
+    });
+    assert_eq!(func.call::<extern fn(isize) -> isize>((6,)).unwrap(), 36);
+    assert!(counter.get() > 0);
+}
@@ -0,0 +1,28 @@
+#![feature(test, plugin)]
+#![plugin(jit_macros)]
+#[no_link] #[macro_use]
+extern crate jit_macros;
+extern crate jit;
+use jit::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[test]
+fn test_breakpoint_hook() {
+    let mut ctx = Context::new();
+    let hits = Rc::new(RefCell::new(Vec::new()));
+    {
+        let hits = hits.clone();
+        debugger::set_hook(&ctx, move |_, data1, data2| {
+            hits.borrow_mut().push((data1, data2));
+        });
+    }
+    let sig = get::<fn(isize) -> isize>();
+    let func = ctx.build_func(sig.get(), |func| {
+        func.insn_mark_breakpoint(debugger::DATA1_ENTER, 0);
+        func.insn_mark_breakpoint_variable(func.insn_of(&debugger::DATA1_LINE), func[0]);
+        func.insn_return(func[0]);
+    });
+    assert_eq!(func.call::<extern fn(isize) -> isize>((7,)).unwrap(), 7);
+    assert_eq!(*hits.borrow(), vec![(debugger::DATA1_ENTER, 0), (debugger::DATA1_LINE, 7)]);
+}
@@ -0,0 +1,28 @@
+#![cfg(all(feature = "disasm", target_arch = "x86_64"))]
+#![feature(test, plugin)]
+#![plugin(jit_macros)]
+#[no_link] #[macro_use]
+extern crate jit_macros;
+extern crate jit;
+use jit::*;
+
+#[test]
+fn test_decode() {
+    assert_eq!(disasm::decode(&[0x55], 0), (1, "push rbp".to_string()));
+    assert_eq!(disasm::decode(&[0x48, 0x89, 0xe5], 0), (3, "mov rbp, rsp".to_string()));
+    assert_eq!(disasm::decode(&[0x48, 0x8b, 0x45, 0xf8], 0), (4, "mov rax, qword ptr [rbp - 0x8]".to_string()));
+    assert_eq!(disasm::decode(&[0xeb, 0x02], 0x10), (2, "jmp 0x14".to_string()));
+    assert_eq!(disasm::decode(&[0xc3], 0), (1, "ret".to_string()));
+    assert_eq!(disasm::decode(&[0x06], 0), (1, "(bad) 0x06".to_string()));
+}
+
+#[test]
+fn test_disassemble() {
+    let mut ctx = ContextBuilder::new().memory_manager(DefaultMemoryManager::new()).build().unwrap();
+    let sig = get::<fn(isize) -> isize>();
+    let func = ctx.build_func(sig.get(), |func| {
+        func.insn_return(func[0] + func[0]);
+    });
+    let text = func.disassemble();
+    assert!(text.contains("ret"));
+}
@@ -0,0 +1,58 @@
+#![feature(test, plugin)]
+#![plugin(jit_macros)]
+#[no_link] #[macro_use]
+extern crate jit_macros;
+extern crate jit;
+use jit::*;
+
+#[test]
+fn test_apply_dyn() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(f64, isize) -> f64>();
+    let func = ctx.build_func(sig.get(), |func| {
+        let times = func.insn_convert(func[1], get::<f64>().get(), false);
+        func.insn_return(func[0] * times);
+    });
+    let result = func.apply_dyn(&[DynValue::Float64(2.5), DynValue::NInt(2)]);
+    assert_eq!(result.unwrap(), DynValue::Float64(5.0));
+}
+
+#[test]
+fn test_apply_dyn_mismatch() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(isize) -> isize>();
+    let func = ctx.build_func(sig.get(), |func| {
+        func.insn_return(func[0]);
+    });
+    match func.apply_dyn(&[]) {
+        Err(ApplyError::ArgCount { expected: 1, found: 0 }) => (),
+        _ => panic!("expected an argument count error")
+    }
+    match func.apply_dyn(&[DynValue::Float64(1.0)]) {
+        Err(ApplyError::ArgType(0)) => (),
+        _ => panic!("expected an argument type error")
+    }
+}
+
+#[test]
+fn test_fits_exact_kind() {
+    let (ptr, long, nint) = (get::<*mut u8>(), get::<i64>(), get::<isize>());
+    assert!(DynValue::Pointer(0 as *mut _).fits(ptr.get()));
+    assert!(!DynValue::Long(0).fits(ptr.get()));
+    assert!(!DynValue::NInt(0).fits(ptr.get()));
+    assert!(!DynValue::Long(0).fits(nint.get()));
+    assert!(DynValue::Long(0).fits(long.get()));
+}
+
+#[test]
+fn test_apply_native() {
+    extern fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+    let sig = get::<fn(i32, i32) -> i32>();
+    let add = add as extern fn(i32, i32) -> i32;
+    let result = unsafe {
+        dynamic::apply(sig.get(), add.as_code(), &[DynValue::Int(1), DynValue::Int(2)])
+    };
+    assert_eq!(result.unwrap(), DynValue::Int(3));
+}
@@ -0,0 +1,42 @@
+#![feature(test, plugin)]
+#![plugin(jit_macros)]
+#[no_link] #[macro_use]
+extern crate jit_macros;
+extern crate jit;
+use jit::*;
+
+#[test]
+fn test_load_store_elem() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(*mut i32, isize) -> i32>();
+    let func = ctx.build_func(sig.get(), |func| {
+        let int = get::<i32>();
+        let elem = func.insn_load_elem(func[0], func[1], int.get());
+        func.insn_store_elem(func[0], func[1], elem * func.insn_of(&2i32));
+        let address = func.insn_load_elem_address(func[0], func[1], int.get());
+        let next = func.insn_add_relative(address, 4);
+        func.insn_return(func.insn_load_relative(next, 0, int.get()));
+    });
+    let mut array = [1i32, 2, 3, 4];
+    assert_eq!(func.call::<extern fn(*mut i32, isize) -> i32>((array.as_mut_ptr(), 1)).unwrap(), 3);
+    assert_eq!(array, [1, 4, 3, 4]);
+}
+
+#[test]
+fn test_load_elem_checked() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(*mut i32, isize, isize) -> i32>();
+    let func = ctx.build_func(sig.get(), |func| {
+        let int = get::<i32>();
+        func.insn_return(func.insn_load_elem_checked(func[0], func[1], func[2], int.get()));
+    });
+    let mut array = [5i32, 6, 7];
+    let ptr = array.as_mut_ptr();
+    assert_eq!(func.call::<extern fn(*mut i32, isize, isize) -> i32>((ptr, 2, 3)).unwrap(), 7);
+    for &index in [3is, -1].iter() {
+        match func.call::<extern fn(*mut i32, isize, isize) -> i32>((ptr, index, 3)) {
+            Err(CallError::Exception(JitException::Builtin(BuiltinException::OutOfBounds))) => (),
+            _ => panic!("expected an out of bounds exception")
+        }
+    }
+}
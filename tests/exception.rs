@@ -0,0 +1,132 @@
+#![feature(libc, test, plugin)]
+#![plugin(jit_macros)]
+#[no_link] #[macro_use]
+extern crate jit_macros;
+extern crate jit;
+extern crate libc;
+use jit::*;
+use libc::c_void;
+use std::ptr;
+
+#[test]
+fn test_try_catch() {
+    let mut ctx = Context::new();
+    jit_func!(ctx, func, catches(x: isize) -> isize, {
+        func.insn_try(|| {
+            func.insn_throw(x);
+        }, |exception| {
+            let value = func.insn_convert(exception, get::<isize>().get(), false);
+            func.insn_return(value);
+        });
+        func.insn_return(func.insn_of(&0is));
+    }, |catches| {
+        assert_eq!(catches(42), 42);
+    });
+}
+
+#[test]
+fn test_try_finally() {
+    let mut ctx = Context::new();
+    jit_func!(ctx, func, square(x: isize) -> isize, {
+        let result = Value::new(func, get::<isize>().get());
+        func.insn_store(result, x);
+        func.insn_try_finally(|| (), |_| (), || {
+            func.insn_store(result, x * x);
+        });
+        func.insn_return(result);
+    }, |square| {
+        assert_eq!(square(3), 9);
+    });
+}
+
+#[test]
+#[should_fail]
+fn test_second_try() {
+    let mut ctx = Context::new();
+    let sig = get::<fn()>();
+    ctx.build_func(sig.get(), |func| {
+        func.insn_try(|| (), |_| ());
+        func.insn_try(|| (), |_| ());
+    });
+}
+
+#[test]
+fn test_division_by_zero() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(isize) -> isize>();
+    let func = ctx.build_func(sig.get(), |func| {
+        let zero = func.insn_of(&0is);
+        func.insn_return(func[0] / zero);
+    });
+    let mut x = 1is;
+    let mut result = 0is;
+    let mut args = [&mut x as *mut isize as *mut c_void];
+    let outcome = unsafe {
+        func.apply(args.as_mut_slice(), &mut result as *mut isize as *mut c_void)
+    };
+    assert_eq!(outcome, Err(JitException::Builtin(BuiltinException::DivisionByZero)));
+}
+
+#[test]
+fn test_thrown_object() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(*mut u8)>();
+    let func = ctx.build_func(sig.get(), |func| {
+        func.insn_throw(func[0]);
+    });
+    let mut data = 42u8;
+    let mut object = &mut data as *mut u8;
+    let mut args = [&mut object as *mut *mut u8 as *mut c_void];
+    let outcome = unsafe {
+        func.apply(args.as_mut_slice(), ptr::null_mut())
+    };
+    assert_eq!(outcome, Err(JitException::Object(object as *mut c_void)));
+}
+
+#[test]
+fn test_stack_trace() {
+    let mut ctx = Context::new();
+    let (trace, func_ptr) = {
+        let sig = get::<fn(isize) -> isize>();
+        let func = ctx.build_func(sig.get(), |func| {
+            let zero = func.insn_of(&0is);
+            func.insn_return(func[0] / zero);
+        });
+        let mut x = 1is;
+        let mut result = 0is;
+        let mut args = [&mut x as *mut isize as *mut c_void];
+        assert!(unsafe {
+            func.apply(args.as_mut_slice(), &mut result as *mut isize as *mut c_void)
+        }.is_err());
+        (exception::take_stack_trace().unwrap(), unsafe { func.as_ptr() })
+    };
+    assert!(trace.frames(&ctx).any(|frame| match frame.function {
+        Some(func) => unsafe { func.as_ptr() == func_ptr },
+        None => false
+    }));
+}
+
+struct SourceFile(&'static str);
+#[test]
+fn test_frame_meta() {
+    let mut ctx = Context::new();
+    let trace = {
+        let sig = get::<fn(isize) -> isize>();
+        let func = ctx.build_func(sig.get(), |func| {
+            func.set_meta(Box::new(SourceFile("divide.src")));
+            func.insn_mark_offset(3);
+            let zero = func.insn_of(&0is);
+            func.insn_return(func[0] / zero);
+        });
+        let mut x = 1is;
+        let mut result = 0is;
+        let mut args = [&mut x as *mut isize as *mut c_void];
+        assert!(unsafe {
+            func.apply(args.as_mut_slice(), &mut result as *mut isize as *mut c_void)
+        }.is_err());
+        exception::take_stack_trace().unwrap()
+    };
+    assert!(trace.frames(&ctx).any(|frame|
+        frame.get_meta::<SourceFile>().map(|file| file.0) == Some("divide.src")
+    ));
+}
@@ -0,0 +1,116 @@
+#![feature(test, plugin)]
+#![plugin(jit_macros)]
+#[no_link] #[macro_use]
+extern crate jit_macros;
+extern crate jit;
+use jit::*;
+
+#[test]
+fn test_instructions() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(i32, i32) -> i32>();
+    ctx.build_func(sig.get(), |func| {
+        let sum = func[0] + func[1];
+        func.insn_return(sum);
+        let block = func.get_entry().unwrap();
+        let opcodes = block.iter().map(|insn| insn.get_opcode()).collect::<Vec<_>>();
+        assert!(opcodes.contains(&Opcode::IAdd));
+        let mut reversed = block.iter_rev().map(|insn| insn.get_opcode()).collect::<Vec<_>>();
+        reversed.reverse();
+        assert_eq!(opcodes, reversed);
+        assert!(block.iter().all(|insn| insn.get_opcode() == Opcode::from_code(insn.get_code())));
+        assert!(!block.iter().any(|insn| insn.is_call()));
+        assert!(func.blocks().count() >= 1);
+        assert!(func.blocks().next() == Some(block));
+        assert_eq!(Opcode::from_name("iadd"), Some(Opcode::IAdd));
+        assert_eq!(Opcode::IAdd.get_name(), Some("iadd"));
+    });
+}
+
+#[test]
+fn test_checked_opcodes() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(i64, i64, f64) -> f64>();
+    ctx.build_func(sig.get(), |func| {
+        let sum = func.insn_add_ovf(func[0], func[1]);
+        let root = func.insn_sqrt(func[2]);
+        let float_sum = func.insn_convert(sum, get::<f64>().get(), false);
+        func.insn_return(float_sum + root);
+        let block = func.get_entry().unwrap();
+        let opcodes = block.iter().map(|insn| insn.get_opcode()).collect::<Vec<_>>();
+        assert!(opcodes.contains(&Opcode::LAddOvf));
+        assert!(opcodes.contains(&Opcode::DSqrt));
+        assert!(opcodes.contains(&Opcode::LongToFloat64));
+        assert!(!opcodes.iter().any(|opcode| match *opcode {
+            Opcode::Other(_) => true,
+            _ => false
+        }));
+        assert_eq!(Opcode::from_name("br_nfge_inv"), Some(Opcode::BrNFGeInv));
+    });
+}
+
+#[test]
+fn test_to_dot() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(isize) -> isize>();
+    ctx.build_func(sig.get(), |func| {
+        let result = Value::new(func, get::<isize>().get());
+        func.insn_store(result, func[0]);
+        func.insn_if(func[0], || {
+            func.insn_store(result, func[0] * func[0]);
+        });
+        func.insn_return(result);
+        let dot = func.to_dot();
+        assert!(dot.starts_with("digraph function {"));
+        assert!(dot.contains("[label=\"branch\"]"));
+        assert!(dot.contains("[label=\"fallthrough\"]"));
+        assert!(dot.trim_right().ends_with("}"));
+    });
+}
+
+#[test]
+fn test_display() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(i32) -> i32>();
+    ctx.build_func(sig.get(), |func| {
+        let three = func.insn_of(&3i32);
+        let sum = func[0] + three;
+        func.insn_return(sum);
+        assert_eq!(format!("{}", func[0]), "arg0");
+        assert_eq!(format!("{}", three), "3");
+        let block = func.get_entry().unwrap();
+        let add = block.iter().find(|insn| insn.get_opcode() == Opcode::IAdd).unwrap();
+        assert_eq!(format!("{}", add), format!("{} = iadd arg0, 3", sum));
+        let text = format!("{}", func);
+        assert!(text.starts_with("function "));
+        assert!(text.contains(&*format!("    {}\n", add)));
+        assert!(add.dump(func).unwrap().contains("iadd"));
+    });
+}
+
+#[test]
+fn test_display_compiled() {
+    let mut ctx = ContextBuilder::new().memory_manager(DefaultMemoryManager::new()).build().unwrap();
+    let sig = get::<fn(i32) -> i32>();
+    let func = ctx.build_func(sig.get(), |func| {
+        func.insn_return(func[0] * func[0]);
+    });
+    let text = format!("{}", func);
+    assert!(text.starts_with(&*format!("function {} compiled to ", sig.get())));
+    assert!(text.lines().count() > 1);
+}
+
+#[test]
+fn test_large_dump() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(i32) -> i32>();
+    ctx.build_func(sig.get(), |func| {
+        let mut sum = func[0];
+        for _ in range(0us, 5000) {
+            sum = sum + func[0];
+        }
+        func.insn_return(sum);
+        // much more than a pipe can hold without being read
+        assert!(func.dump().unwrap().len() > 100000);
+    });
+}
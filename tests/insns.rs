@@ -0,0 +1,46 @@
+#![feature(test, plugin)]
+#![plugin(jit_macros)]
+#[no_link] #[macro_use]
+extern crate jit_macros;
+extern crate jit;
+use jit::*;
+
+#[test]
+fn test_insn_coverage() {
+    let sys = include_str!("../sys/lib.rs");
+    let mut bound = sys.split("pub fn ").skip(1)
+        .map(|rest| rest.split('(').next().unwrap())
+        .filter(|name| name.starts_with("jit_insn_"))
+        .collect::<Vec<_>>();
+    let mut wrapped = INSN_FUNCTIONS.to_vec();
+    bound.sort();
+    wrapped.sort();
+    assert_eq!(wrapped, bound);
+}
+
+#[test]
+fn test_compare_and_shift() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(i32, i32) -> i32>();
+    let func = ctx.build_func(sig.get(), |func| {
+        let cmp = func.insn_cmpl(func[0], func[1]);
+        let shifted = func.insn_sshr(func[0], func.insn_of(&1i32));
+        let is_set = func.insn_to_bool(func[1]);
+        func.insn_return(cmp * func.insn_of(&100i32) + shifted * func.insn_of(&10i32) + is_set);
+    });
+    assert_eq!(func.call::<extern fn(i32, i32) -> i32>((-4, 3)).unwrap(), -100 - 20 + 1);
+    assert_eq!(func.call::<extern fn(i32, i32) -> i32>((8, 0)).unwrap(), 100 + 40);
+}
+
+#[test]
+fn test_check_null() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(*mut u8)>();
+    let func = ctx.build_func(sig.get(), |func| {
+        func.insn_check_null(func[0]);
+    });
+    match func.call::<extern fn(*mut u8)>((std::ptr::null_mut(),)) {
+        Err(CallError::Exception(JitException::Builtin(BuiltinException::NullReference))) => (),
+        _ => panic!("expected a null reference exception")
+    }
+}
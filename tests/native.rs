@@ -0,0 +1,63 @@
+#![feature(test, plugin)]
+#![plugin(jit_macros)]
+#[no_link] #[macro_use]
+extern crate jit_macros;
+extern crate jit;
+use jit::*;
+
+#[test]
+fn test_native_code() {
+    let mut ctx = ContextBuilder::new().memory_manager(DefaultMemoryManager::new()).build().unwrap();
+    let sig = get::<fn(isize) -> isize>();
+    let func = ctx.build_func(sig.get(), |func| {
+        func.insn_return(func[0] * func[0]);
+    });
+    let code = func.native_code();
+    assert!(code.len() > 0);
+    let entry = unsafe { func.get_fn::<extern fn(isize) -> isize>().unwrap() };
+    assert_eq!(code.as_ptr() as usize, entry as usize);
+}
+
+#[test]
+fn test_native_code_untracked() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(isize) -> isize>();
+    let func = ctx.build_func(sig.get(), |func| {
+        func.insn_return(func[0] * func[0]);
+    });
+    assert_eq!(func.native_code().len(), 0);
+}
+
+#[test]
+fn test_native_code_on_demand() {
+    let mut ctx = ContextBuilder::new().memory_manager(DefaultMemoryManager::new()).build().unwrap();
+    let sig = get::<fn(isize) -> isize>();
+    let func = ctx.build_on_demand(sig.get(), |func| {
+        func.insn_return(func[0] * func[0]);
+    });
+    assert_eq!(func.native_code().len(), 0);
+    assert_eq!(func.call::<extern fn(isize) -> isize>((3,)).unwrap(), 9);
+    assert!(func.native_code().len() > 0);
+}
+
+#[test]
+fn test_pc_mapping() {
+    let mut ctx = ContextBuilder::new().memory_manager(DefaultMemoryManager::new()).build().unwrap();
+    let (start, len) = {
+        let sig = get::<fn(isize) -> isize>();
+        let func = ctx.build_func(sig.get(), |func| {
+            func.insn_mark_offset(7);
+            func.insn_return(func[0] * func[0]);
+        });
+        let code = func.native_code();
+        let pcs = range(0, code.len()).map(|i| unsafe { code.as_ptr().offset(i as isize) as *mut _ });
+        let offsets = pcs.map(|pc| func.offset_for_pc(pc)).collect::<Vec<_>>();
+        assert!(offsets.contains(&Some(7)));
+        assert!(offsets.iter().all(|&offset| offset == None || offset == Some(7)));
+        assert_eq!(func.offset_for_pc(test_pc_mapping as *mut _), None);
+        (code.as_ptr() as *mut _, code.len())
+    };
+    assert!(ctx.function_from_pc(start).is_some());
+    assert!(ctx.function_from_pc(unsafe { start.offset(len as isize - 1) }).is_some());
+    assert!(ctx.function_from_pc(test_pc_mapping as *mut _).is_none());
+}
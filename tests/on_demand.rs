@@ -0,0 +1,48 @@
+#![feature(test, plugin)]
+#![plugin(jit_macros)]
+#[no_link] #[macro_use]
+extern crate jit_macros;
+extern crate jit;
+use jit::*;
+use std::cell::Cell;
+use std::rc::Rc;
+
+#[test]
+fn test_on_demand() {
+    let mut ctx = Context::new();
+    let builds = Rc::new(Cell::new(0));
+    let sig = get::<fn(isize) -> isize>();
+    let func = {
+        let builds = builds.clone();
+        ctx.build_on_demand(sig.get(), move |func| {
+            builds.set(builds.get() + 1);
+            func.insn_return(func[0] * func[0]);
+        })
+    };
+    assert_eq!(builds.get(), 0);
+    assert!(!func.is_compiled());
+    assert_eq!(func.call::<extern fn(isize) -> isize>((3,)).unwrap(), 9);
+    assert_eq!(func.call::<extern fn(isize) -> isize>((4,)).unwrap(), 16);
+    assert_eq!(builds.get(), 1);
+    assert!(func.is_compiled());
+}
+
+#[test]
+fn test_on_demand_driver() {
+    let mut ctx = Context::new();
+    let drives = Rc::new(Cell::new(0));
+    {
+        let drives = drives.clone();
+        ctx.set_on_demand_driver(move |func, compile| {
+            assert!(!func.is_compiled());
+            drives.set(drives.get() + 1);
+            compile();
+        });
+    }
+    let sig = get::<fn(isize) -> isize>();
+    let func = ctx.build_on_demand(sig.get(), |func| {
+        func.insn_return(func[0] + func[0]);
+    });
+    assert_eq!(func.call::<extern fn(isize) -> isize>((21,)).unwrap(), 42);
+    assert_eq!(drives.get(), 1);
+}
@@ -0,0 +1,45 @@
+#![feature(test, plugin)]
+#![plugin(jit_macros)]
+#[no_link] #[macro_use]
+extern crate jit_macros;
+extern crate jit;
+use jit::*;
+
+#[test]
+fn test_constant_ops() {
+    let mut ctx = Context::new();
+    jit_func!(ctx, func, affine(x: isize) -> isize, {
+        func.insn_return((x + 3is) * 2is - 1is);
+    }, |affine| {
+        assert_eq!(affine(4), 13);
+        assert_eq!(affine(-3), -1);
+    });
+}
+
+#[test]
+fn test_comparisons() {
+    let mut ctx = Context::new();
+    jit_func!(ctx, func, compare(a: i32, b: i32) -> i32, {
+        let bits = [a.lt(b), a.le(b), a.gt(b), a.ge(b), a.eq_v(b), a.ne_v(b)];
+        let mut result = func.insn_of(&0i32);
+        for (shift, bit) in bits.iter().enumerate() {
+            result = result | (*bit << shift as i32);
+        }
+        func.insn_return(result);
+    }, |compare| {
+        assert_eq!(compare(1, 2), 0b100011);
+        assert_eq!(compare(2, 2), 0b011010);
+        assert_eq!(compare(3, 2), 0b101100);
+    });
+}
+
+#[test]
+fn test_constant_comparisons() {
+    let mut ctx = Context::new();
+    jit_func!(ctx, func, at_least_half(x: f64) -> i32, {
+        func.insn_return(x.ge(0.5f64));
+    }, |at_least_half| {
+        assert_eq!(at_least_half(0.25), 0);
+        assert_eq!(at_least_half(0.5), 1);
+    });
+}
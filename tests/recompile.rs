@@ -0,0 +1,29 @@
+#![feature(test, plugin)]
+#![plugin(jit_macros)]
+#[no_link] #[macro_use]
+extern crate jit_macros;
+extern crate jit;
+use jit::*;
+
+#[test]
+fn test_recompile() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(isize) -> isize>();
+    let func = ctx.build_func(sig.get(), |func| {
+        func.set_recompilable();
+        func.set_optimization_level(0);
+        func.insn_return(func[0] + func.insn_of(&1is));
+    });
+    assert!(func.is_recompilable());
+    assert_eq!(func.get_optimization_level(), 0);
+    assert_eq!(func.call::<extern fn(isize) -> isize>((1,)).unwrap(), 2);
+    let max_level = UncompiledFunction::get_max_optimization_level();
+    assert!(func.recompile(|func| {
+        func.set_optimization_level(max_level);
+        func.insn_return(func[0] * func.insn_of(&10is));
+    }));
+    assert_eq!(func.get_optimization_level(), max_level);
+    assert_eq!(func.call::<extern fn(isize) -> isize>((1,)).unwrap(), 10);
+    func.clear_recompilable();
+    assert!(!func.is_recompilable());
+}
@@ -0,0 +1,68 @@
+#![feature(test, plugin)]
+#![plugin(jit_macros)]
+#[no_link] #[macro_use]
+extern crate jit_macros;
+extern crate jit;
+use jit::*;
+
+static NUMBERS: [i32; 4] = [1, 2, 3, 4];
+
+#[test]
+fn test_slice_sum() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(*const i32, usize) -> i32>();
+    let func = ctx.build_func(sig.get(), |func| {
+        let int = get::<i32>();
+        let slice = JitSlice::<i32>::new(func[0], func[1]);
+        let total = Value::new(func, int.get());
+        func.insn_store(total, func.insn_of(&0i32));
+        slice.for_each(|elem| func.insn_store(total, total + elem));
+        func.insn_return(total);
+    });
+    let slice = &NUMBERS[..];
+    assert_eq!(func.call::<extern fn(*const i32, usize) -> i32>((slice.as_ptr(), slice.len())).unwrap(), 10);
+}
+
+#[test]
+fn test_slice_index() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(*mut i32, usize, usize) -> i32>();
+    let func = ctx.build_func(sig.get(), |func| {
+        let slice = JitSlice::<i32>::new(func[0], func[1]);
+        let elem = slice.index(func[2]);
+        slice.index_set(func[2], elem + func.insn_of(&1i32));
+        func.insn_return(slice.index(func[2]));
+    });
+    let mut array = [5i32, 6, 7];
+    let ptr = array.as_mut_ptr();
+    assert_eq!(func.call::<extern fn(*mut i32, usize, usize) -> i32>((ptr, 3, 1)).unwrap(), 7);
+    assert_eq!(array, [5, 7, 7]);
+    match func.call::<extern fn(*mut i32, usize, usize) -> i32>((ptr, 3, 3)) {
+        Err(CallError::Exception(JitException::Builtin(BuiltinException::OutOfBounds))) => (),
+        _ => panic!("expected an out of bounds exception")
+    }
+}
+
+#[test]
+fn test_compile_slice() {
+    let mut ctx = Context::new();
+    let sig = get::<fn() -> usize>();
+    let func = ctx.build_func(sig.get(), |func| {
+        let slice = JitSlice::<i32>::from_value(func.insn_of(&&NUMBERS[..]));
+        func.insn_return(slice.len());
+    });
+    assert_eq!(func.call::<extern fn() -> usize>(()).unwrap(), 4);
+}
+
+#[test]
+fn test_raw_slice_param() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(RawSlice<i32>) -> usize>();
+    let func = ctx.build_func(sig.get(), |func| {
+        let slice = JitSlice::<i32>::from_value(func[0]);
+        func.insn_return(slice.len());
+    });
+    let numbers = vec![3i32, 4, 5];
+    let slice = RawSlice::new(&numbers[..]);
+    assert_eq!(func.call::<extern fn(RawSlice<i32>) -> usize>((slice,)).unwrap(), 3);
+}
@@ -18,4 +18,42 @@ fn test_struct() {
             _ => unimplemented!()
         })
     }
-}
\ No newline at end of file
+}
+#[jit]
+#[repr(packed)]
+struct Pos {
+    x: f64,
+    y: f64
+}
+
+#[test]
+fn test_field_access() {
+    let pos_t = get::<Pos>();
+    assert_eq!(pos_t.get().get_field("y").get_offset(), 8);
+    assert!(pos_t.get().find_field("y").map(|field| field.get_offset()) == Some(8));
+    assert!(pos_t.get().find_field("z").is_none());
+    let mut ctx = Context::new();
+    let sig = get::<fn(*mut Pos) -> f64>();
+    let func = ctx.build_func(sig.get(), |func| {
+        let pos = func[0];
+        pos.set_field("y", pos.field("x").unwrap() * pos.field("y").unwrap()).unwrap();
+        let local = Value::new(func, pos_t.get());
+        local.set_field("x", pos.field("y").unwrap()).unwrap();
+        local.set_field("y", func.insn_of(&1f64)).unwrap();
+        func.insn_return(local.field("x").unwrap() + local.field("y").unwrap());
+    });
+    let mut pos = Pos { x: 3.0, y: 4.0 };
+    assert_eq!(func.call::<extern fn(*mut Pos) -> f64>((&mut pos as *mut Pos,)).unwrap(), 13.0);
+    assert_eq!(pos.y, 12.0);
+}
+
+#[test]
+fn test_unknown_field() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(*mut Pos) -> f64>();
+    ctx.build_func(sig.get(), |func| {
+        assert_eq!(func[0].field("z").err(), Some(FieldError::UnknownField("z".to_string())));
+        assert_eq!(func.insn_of(&1f64).field("x").err(), Some(FieldError::NotStruct));
+        func.insn_return(func[0].field("x").unwrap());
+    });
+}
@@ -7,9 +7,45 @@ use jit::*;
 
 #[test]
 fn test_context_tags() {
-    let ctx = Context::new();
+    let mut ctx = Context::new();
     ctx.set_meta(Box::new(3u8));
     assert_eq!(ctx.get_meta(), Some(&3u8));
+    ctx.set_meta(Box::new(3u16));
+    assert_eq!(ctx.get_meta(), Some(&3u8));
+    assert_eq!(ctx.remove_meta::<u16>(), Some(Box::new(3u16)));
+    assert_eq!(ctx.get_meta::<u16>(), None);
+}
+
+#[test]
+fn test_replaced_tags() {
+    let ctx = Context::new();
+    ctx.set_meta(Box::new(3u8));
+    let old = ctx.get_meta::<u8>();
+    ctx.set_meta(Box::new(4u8));
+    assert_eq!(old, Some(&3u8));
+    assert_eq!(ctx.get_meta(), Some(&4u8));
+}
+
+#[test]
+fn test_context_options() {
+    let ctx = Context::new();
+    ctx.set_option(ContextOption::DontFold, 1);
+    assert_eq!(ctx.get_option(ContextOption::DontFold), 1);
+}
+
+#[test]
+fn test_function_tags() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(isize) -> isize>();
+    let func = ctx.build_func(sig.get(), |func| {
+        func.set_meta(Box::new("square"));
+        let block = func.get_current().unwrap();
+        block.set_meta(Box::new(7us));
+        assert_eq!(block.get_meta(), Some(&7us));
+        func.insn_return(func[0] * func[0]);
+    });
+    assert_eq!(func.get_meta(), Some(&"square"));
+    assert_eq!(func.get_meta::<usize>(), None);
 }
 
 #[derive(Debug, Eq, PartialEq)]
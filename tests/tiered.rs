@@ -0,0 +1,26 @@
+#![feature(test, plugin)]
+#![plugin(jit_macros)]
+#[no_link] #[macro_use]
+extern crate jit_macros;
+extern crate jit;
+use jit::*;
+
+#[test]
+fn test_tiered() {
+    let mut ctx = Context::new();
+    let sig = get::<fn(isize) -> isize>();
+    let func = TieredFunction::new(&mut ctx, sig.get(), 3, |func| {
+        func.insn_return(func[0] * func[0]);
+    });
+    assert_eq!(func.get_optimization_level(), 0);
+    for x in range(0is, 2) {
+        assert_eq!(func.call::<extern fn(isize) -> isize>((x,)).unwrap(), x * x);
+    }
+    assert!(!func.is_promoted());
+    assert_eq!(func.call::<extern fn(isize) -> isize>((2,)).unwrap(), 4);
+    assert!(func.is_promoted());
+    assert_eq!(func.get_calls(), 3);
+    assert_eq!(func.get_optimization_level(), UncompiledFunction::get_max_optimization_level());
+    assert_eq!(func.call::<extern fn(isize) -> isize>((5,)).unwrap(), 25);
+    assert_eq!(func.get_calls(), 3);
+}
@@ -0,0 +1,40 @@
+extern crate jit;
+use jit::vmem::{self, flags, VMem};
+
+#[test]
+fn test_rounding() {
+    let page = vmem::page_size();
+    assert!(page.is_power_of_two());
+    assert_eq!(vmem::round_up(1), page);
+    assert_eq!(vmem::round_up(page), page);
+    assert_eq!(vmem::round_down(page + 1), page);
+}
+
+#[test]
+fn test_commit() {
+    let page = vmem::page_size();
+    let mut region = VMem::reserve(page * 2).unwrap();
+    assert_eq!(region.len(), page * 2);
+    assert!(region.commit(0, page, flags::READ | flags::WRITE));
+    unsafe {
+        *region.as_ptr() = 42;
+        assert_eq!(*region.as_ptr(), 42);
+    }
+    assert!(region.protect(0, page, flags::READ));
+    assert!(region.decommit(0, page));
+}
+
+#[test]
+#[cfg(target_pointer_width = "64")]
+fn test_reserve_too_big() {
+    assert!(VMem::reserve(1 << 32).is_none());
+    assert!(VMem::reserve_committed(1 << 32, flags::READ).is_none());
+}
+
+#[test]
+#[should_fail]
+fn test_commit_overflow() {
+    let page = vmem::page_size();
+    let mut region = VMem::reserve(page).unwrap();
+    region.commit(page, !0, flags::READ);
+}